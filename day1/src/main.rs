@@ -4,13 +4,43 @@
 // Notes:
 //  The Elves track their calories in input.txt
 //  Each line is an amount of calories. The double line breaks separate each Elf
+//  Elves are numbered from 1 in the order they appear in the file
+//  --stats prints a report on all the elves: how many items they carry, the mean, median and percentiles of their
+//    calories, a histogram (--buckets <n> sets the number of bars, default 10) and every elf ranked by calories.
+//    --csv <file> saves the ranked table as CSV
+//  The file is read line by line and the elves are added up as they are read, so the whole file is never in memory.
+//    Any number of blank lines can separate elves, the last elf doesn't need a blank line after it and CRLF line
+//    endings work. Totals are u64 and adding past that is an error rather than wrapping around.
+//  --on-bad-line skip|fail picks what happens to a line that isn't a number: skip prints a warning and ignores it
+//    (the default), fail stops with an error
 
 // Questions:
 // 1. What is the maximum calories that a single elf is carrying?
 // 2. How many calories are the top 3 elves carrying?
 
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
 use std::env;
 use std::fs;
+use std::io::{BufRead, BufReader};
+mod stats;
+
+const DEFAULT_HISTOGRAM_BUCKETS: usize = 10;
+
+// what one elf is carrying
+pub struct Elf {
+	pub item_count: usize,
+	pub calories: u64,
+}
+
+// what to do with a line that isn't a number of calories
+#[derive(Clone, Copy)]
+enum BadLinePolicy {
+	// print a warning and carry on without it
+	Skip,
+	// stop reading with an error
+	Fail,
+}
 
 fn main() {
 	// get file path from commandline input
@@ -21,71 +51,152 @@ fn main() {
 
 	let input_file = &args[1];
 
-	let file_contents = fs::read_to_string(input_file);
-	let file_contents = match file_contents {
-		Ok(contents) => contents,
-		Err(_) => panic!("Could not find input file {}", input_file),
+	let file = match fs::File::open(input_file) {
+		Ok(file) => file,
+		Err(err) => panic!("Could not open input file {}. Reason: {}", input_file, err),
+	};
+
+	let policy = match flag_value(&args, "--on-bad-line").unwrap_or("skip") {
+		"skip" => BadLinePolicy::Skip,
+		"fail" => BadLinePolicy::Fail,
+		other => panic!("Unknown --on-bad-line policy '{}'. Expected skip or fail", other),
 	};
 
-	let calories = process_calories(file_contents);
+	let elves = match ElfGrouper::new(BufReader::new(file), policy).collect::<Result<Vec<Elf>, String>>() {
+		Ok(elves) => elves,
+		Err(err) => panic!("Could not read the elves' calories. Reason: {}", err),
+	};
 
 	println!("#############################");
 	println!("### Advent of Code, Day 1 ###");
 	println!("#############################");
-	println!("The elf with the most calories has {}", get_max_calories(&calories).0);
-	println!("The top 3 elves have a total of {}", get_top_3_elf_calories(&calories));
-}
 
-// converts the file input contents into a Vec<u32> representing the calories of each elf
-fn process_calories(calories: String) -> Vec<u32> {
-	let mut elf_calories: Vec<u32> = Vec::new();
-	let mut current_elf: u32 = 0;
-	
-	for line in calories.lines(){
-		let trimmed_line = line.trim();
-		if trimmed_line == "" {
-			elf_calories.push(current_elf);
-			current_elf = 0;
+	let top_3 = top_k(elves.iter().map(|elf| elf.calories), 3);
+	match top_3.first() {
+		Some((max, index)) => println!("The elf with the most calories is elf {} with {}", index + 1, max),
+		None => println!("There are no elves in the input"),
+	}
+	let top_3_elves = top_3.iter().map(|(_, index)| (index + 1).to_string()).collect::<Vec<String>>();
+	println!("The top 3 elves ({}) have a total of {}",
+		top_3_elves.join(", "),
+		top_3.iter().map(|(calorie, _)| *calorie as u128).sum::<u128>());
+
+	let show_stats = args.iter().any(|arg| arg == "--stats");
+	let csv_file = flag_value(&args, "--csv");
+	if show_stats || csv_file.is_some() {
+		let buckets = match flag_value(&args, "--buckets").map(|buckets| buckets.parse::<usize>()) {
+			Some(Ok(buckets)) if buckets > 0 => buckets,
+			Some(_) => panic!("--buckets expects a number greater than 0"),
+			None => DEFAULT_HISTOGRAM_BUCKETS,
+		};
+		let report = match stats::Report::new(&elves, buckets) {
+			Some(report) => report,
+			None => panic!("Can't make a report without any elves"),
+		};
+
+		if show_stats {
+			println!();
+			print!("{}", report.to_text());
 		}
-		else {
-			let calorie = trimmed_line.parse::<u32>();
-			match calorie {
-				Ok(v) => current_elf += v,
-				Err(e) => println!("Could not parse line from file: '{}'. Error: {}", trimmed_line, e),
+		if let Some(file) = csv_file {
+			if let Err(err) = fs::write(file, report.to_csv()) {
+				panic!("Could not write CSV file {}. Reason: {}", file, err);
 			}
 		}
 	}
+}
 
-	elf_calories
+// returns the argument following the given flag, e.g. "out.csv" for "--csv out.csv"
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+	let index = args.iter().position(|arg| arg == flag)?;
+	match args.get(index + 1) {
+		Some(value) => Some(value.as_str()),
+		None => panic!("{} expects a value", flag),
+	}
 }
 
-// return type: (max_calories, index)
-fn get_max_calories(calories: &Vec<u32>) -> (u32, usize) {
-	let mut max: u32 = 0;
-	let mut index: usize = 0;
+// reads elves from a file one at a time, each one as soon as its last line has been read
+struct ElfGrouper<R: BufRead> {
+	lines: std::io::Lines<R>,
+	policy: BadLinePolicy,
+	line_number: usize,
+}
 
-	for (idx, calorie) in calories.iter().enumerate() {
-		if *calorie > max {
-			max = *calorie;
-			index = idx;
+impl<R: BufRead> ElfGrouper<R> {
+	fn new(reader: R, policy: BadLinePolicy) -> Self {
+		ElfGrouper {
+			lines: reader.lines(),
+			policy,
+			line_number: 0,
 		}
 	}
-	return (max, index);
 }
 
-// returns the total calories of the top 3 calorie dense elves
-fn get_top_3_elf_calories(calories: &Vec<u32>) -> u32 {
-	let mut calories_clone = calories.clone();
-	let mut index_to_remove: usize;
-	let elf_1: u32;
-	let elf_2: u32;
-	let elf_3: u32;
-	
-	(elf_1, index_to_remove) = get_max_calories(&calories_clone);
-	calories_clone.remove(index_to_remove);
-	(elf_2, index_to_remove) = get_max_calories(&calories_clone);
-	calories_clone.remove(index_to_remove);
-	(elf_3, _) = get_max_calories(&calories_clone);
-
-	elf_1 + elf_2 + elf_3
-}
\ No newline at end of file
+impl<R: BufRead> Iterator for ElfGrouper<R> {
+	type Item = Result<Elf, String>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		// None until the first non-blank line of the next elf
+		let mut current_elf: Option<Elf> = None;
+
+		// lines() strips both \n and \r\n
+		for line in self.lines.by_ref() {
+			self.line_number += 1;
+			let line = match line {
+				Ok(line) => line,
+				Err(err) => return Some(Err(format!("line {}: {}", self.line_number, err))),
+			};
+
+			let trimmed_line = line.trim();
+			if trimmed_line.is_empty() {
+				// blank lines end an elf. Extra ones before the next elf are skipped
+				match current_elf {
+					Some(elf) => return Some(Ok(elf)),
+					None => continue,
+				}
+			}
+
+			match trimmed_line.parse::<u64>() {
+				Ok(calories) => {
+					// only a line that parses starts an elf, so a group of skipped lines isn't an elf
+					let elf = current_elf.get_or_insert(Elf { item_count: 0, calories: 0 });
+					elf.item_count += 1;
+					elf.calories = match elf.calories.checked_add(calories) {
+						Some(total) => total,
+						None => return Some(Err(format!("line {}: the elf's total is more than {} calories",
+							self.line_number, u64::MAX))),
+					};
+				}
+				Err(err) => match self.policy {
+					BadLinePolicy::Skip => eprintln!("Skipping line {}: '{}'. Error: {}", self.line_number, trimmed_line, err),
+					BadLinePolicy::Fail => return Some(Err(format!("line {}: '{}' is not a number of calories. Error: {}",
+						self.line_number, trimmed_line, err))),
+				},
+			}
+		}
+
+		// the last elf isn't followed by a blank line if the file doesn't end with one
+		current_elf.map(Ok)
+	}
+}
+
+// returns the k elves carrying the most calories, most calories first
+// return type: Vec<(calories, index)>
+// keeps a min-heap of the best k seen so far, so the smallest of them is the one to replace
+fn top_k(calories: impl IntoIterator<Item = u64>, k: usize) -> Vec<(u64, usize)> {
+	let mut heap: BinaryHeap<Reverse<(u64, Reverse<usize>)>> = BinaryHeap::with_capacity(k + 1);
+
+	for (idx, calorie) in calories.into_iter().enumerate() {
+		// on ties the elf that comes first wins, so Reverse the index to make it the bigger one
+		heap.push(Reverse((calorie, Reverse(idx))));
+		if heap.len() > k {
+			heap.pop();
+		}
+	}
+
+	// sorting the Reverse'd entries ascending puts the most calories first
+	heap.into_sorted_vec()
+		.into_iter()
+		.map(|Reverse((calorie, Reverse(idx)))| (calorie, idx))
+		.collect()
+}
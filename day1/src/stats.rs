@@ -0,0 +1,157 @@
+use super::Elf;
+
+// percentiles of the calorie totals shown in the report. The 50th is the median
+const PERCENTILES: [f64; 5] = [10.0, 25.0, 50.0, 75.0, 90.0];
+// width of the longest histogram bar in chars
+const HISTOGRAM_WIDTH: usize = 40;
+
+pub struct Report {
+	elves: usize,
+	total_items: usize,
+	total_calories: u128,
+	min_items: usize,
+	max_items: usize,
+	mean_items: f64,
+	mean_calories: f64,
+	median_calories: f64,
+	// (percentile, calories)
+	percentiles: Vec<(f64, f64)>,
+	histogram: Vec<Bucket>,
+	ranking: Vec<RankedElf>,
+}
+
+// elves whose totals are between low and high, inclusive
+struct Bucket {
+	low: u64,
+	high: u64,
+	count: usize,
+}
+
+struct RankedElf {
+	rank: usize,
+	// numbered from 1 in file order
+	elf: usize,
+	items: usize,
+	calories: u64,
+	// share of all the calories carried by every elf, in percent
+	share: f64,
+}
+
+impl Report {
+	// returns None if there are no elves to report on
+	pub fn new(elves: &[Elf], buckets: usize) -> Option<Self> {
+		if elves.is_empty() {
+			return None;
+		}
+
+		let mut sorted_calories = elves.iter().map(|elf| elf.calories).collect::<Vec<u64>>();
+		sorted_calories.sort_unstable();
+		let item_counts = elves.iter().map(|elf| elf.item_count).collect::<Vec<usize>>();
+		let total_items: usize = item_counts.iter().sum();
+		// u128 so adding up many u64 totals can't overflow
+		let total_calories: u128 = sorted_calories.iter().map(|calories| *calories as u128).sum();
+
+		Some(Report {
+			elves: elves.len(),
+			total_items,
+			total_calories,
+			min_items: *item_counts.iter().min()?,
+			max_items: *item_counts.iter().max()?,
+			mean_items: total_items as f64 / elves.len() as f64,
+			mean_calories: total_calories as f64 / elves.len() as f64,
+			median_calories: percentile(&sorted_calories, 50.0),
+			percentiles: PERCENTILES.iter().map(|p| (*p, percentile(&sorted_calories, *p))).collect(),
+			histogram: histogram(&sorted_calories, buckets),
+			ranking: rank(elves, total_calories),
+		})
+	}
+
+	pub fn to_text(&self) -> String {
+		let mut s = String::new();
+		s += &format!("Elves: {}\n", self.elves);
+		s += &format!("Items: {} in total, {} to {} per elf, {:.2} on average\n",
+			self.total_items, self.min_items, self.max_items, self.mean_items);
+		s += &format!("Calories: {} in total, {:.2} mean, {:.1} median\n",
+			self.total_calories, self.mean_calories, self.median_calories);
+		for (p, calories) in &self.percentiles {
+			s += &format!("  {:>2}th percentile: {:.1}\n", p, calories);
+		}
+
+		s += "\nHistogram of calories per elf:\n";
+		let most = self.histogram.iter().map(|bucket| bucket.count).max().unwrap_or(0);
+		let label_width = self.histogram.last().map_or(0, |bucket| bucket.high.to_string().len());
+		for bucket in &self.histogram {
+			// round up so a bucket with any elves in it always gets a bar
+			let bar = (bucket.count * HISTOGRAM_WIDTH).div_ceil(most.max(1));
+			s += &format!("  {:>w$} - {:>w$} | {:<bw$} {}\n",
+				bucket.low, bucket.high, "#".repeat(bar), bucket.count, w = label_width, bw = HISTOGRAM_WIDTH);
+		}
+
+		s += "\nElves ranked by calories:\n";
+		s += "  rank    elf  items  calories  share\n";
+		for ranked in &self.ranking {
+			s += &format!("  {:>4}  {:>5}  {:>5}  {:>8}  {:>5.2}%\n",
+				ranked.rank, ranked.elf, ranked.items, ranked.calories, ranked.share);
+		}
+		s
+	}
+
+	// the ranked table, one row per elf
+	pub fn to_csv(&self) -> String {
+		let mut s = String::from("rank,elf,items,calories,share_percent\n");
+		for ranked in &self.ranking {
+			s += &format!("{},{},{},{},{:.4}\n", ranked.rank, ranked.elf, ranked.items, ranked.calories, ranked.share);
+		}
+		s
+	}
+}
+
+// linear interpolation between the two closest ranks. sorted must not be empty
+fn percentile(sorted: &[u64], p: f64) -> f64 {
+	let position = p / 100.0 * (sorted.len() - 1) as f64;
+	let below = position.floor() as usize;
+	let above = position.ceil() as usize;
+	let fraction = position - below as f64;
+	sorted[below] as f64 + (sorted[above] as f64 - sorted[below] as f64) * fraction
+}
+
+// splits min..=max into equally wide buckets. sorted must not be empty
+fn histogram(sorted: &[u64], buckets: usize) -> Vec<Bucket> {
+	// u128 so max - min + 1 can't overflow when the totals span all of u64
+	let min = sorted[0] as u128;
+	let max = sorted[sorted.len() - 1] as u128;
+	let width = (max - min + 1).div_ceil(buckets as u128);
+
+	let mut histogram = vec![];
+	let mut low = min;
+	while low <= max {
+		let high = (low + width - 1).min(max);
+		histogram.push(Bucket {
+			low: low as u64,
+			high: high as u64,
+			count: sorted.iter().filter(|calories| (low..=high).contains(&(**calories as u128))).count(),
+		});
+		low = high + 1;
+	}
+	histogram
+}
+
+// most calories first. Ties keep file order
+fn rank(elves: &[Elf], total_calories: u128) -> Vec<RankedElf> {
+	let mut order = (0..elves.len()).collect::<Vec<usize>>();
+	order.sort_by_key(|index| std::cmp::Reverse(elves[*index].calories));
+
+	order.iter()
+		.enumerate()
+		.map(|(position, index)| RankedElf {
+			rank: position + 1,
+			elf: index + 1,
+			items: elves[*index].item_count,
+			calories: elves[*index].calories,
+			share: match total_calories {
+				0 => 0.0,
+				total => elves[*index].calories as f64 / total as f64 * 100.0,
+			},
+		})
+		.collect()
+}
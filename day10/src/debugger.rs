@@ -0,0 +1,213 @@
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+use super::{Cpu, CrtScreen, Instruction};
+
+// lists the program with the address of each instruction and the cycle it starts on
+pub fn disassemble(instructions: &[Instruction]) -> String {
+	let mut listing = String::new();
+	let mut start_cycle = 1;
+
+	for (address, instruction) in instructions.iter().enumerate() {
+		listing.push_str(&format!("{:04}  cycle {:>4}  {}\n", address, start_cycle, instruction));
+		start_cycle += instruction.cycles();
+	}
+	listing
+}
+
+pub fn print_trace(instructions: &[Instruction], screen: &CrtScreen) {
+	let mut stepper = Stepper::new(instructions, screen);
+	while let Some(entry) = stepper.step() {
+		println!("{}", entry);
+	}
+}
+
+pub struct TraceEntry {
+	cycle: usize,
+	address: usize,
+	instruction: Instruction,
+	cycle_of_instruction: usize,
+	register_before: i32,
+	register_after: i32,
+	pixel: Option<(usize, usize, char)>,
+}
+
+impl fmt::Display for TraceEntry {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "cycle {:>4} | {:04} {:<10} ({}/{}) | X {:>3} -> {:>3} | ",
+			self.cycle,
+			self.address,
+			self.instruction.to_string(),
+			self.cycle_of_instruction,
+			self.instruction.cycles(),
+			self.register_before,
+			self.register_after)?;
+
+		match self.pixel {
+			Some((column, row, pixel)) => write!(f, "pixel ({}, {}) '{}'", column, row, pixel),
+			None => write!(f, "off screen"),
+		}
+	}
+}
+
+// executes a program one cycle at a time so it can be inspected between cycles
+pub struct Stepper<'a> {
+	instructions: &'a [Instruction],
+	screen: &'a CrtScreen,
+	cpu: Cpu,
+	address: usize,
+	cycle_of_instruction: usize,
+}
+
+impl<'a> Stepper<'a> {
+	pub fn new(instructions: &'a [Instruction], screen: &'a CrtScreen) -> Self {
+		Stepper {
+			instructions,
+			screen,
+			cpu: Cpu::new(),
+			address: 0,
+			cycle_of_instruction: 0,
+		}
+	}
+
+	// number of cycles that have completed so far
+	pub fn cycle(&self) -> usize {
+		self.cpu.register_history.len()
+	}
+
+	pub fn is_finished(&self) -> bool {
+		self.address >= self.instructions.len()
+	}
+
+	pub fn step(&mut self) -> Option<TraceEntry> {
+		let instruction = self.instructions.get(self.address)?;
+
+		self.cycle_of_instruction += 1;
+		let is_final_cycle = self.cycle_of_instruction == instruction.cycles();
+		let register_before = self.cpu.register;
+		let pixel = self.screen.pixel_at(self.cycle(), register_before);
+
+		self.cpu.tick(instruction, is_final_cycle);
+
+		let entry = TraceEntry {
+			cycle: self.cycle(),
+			address: self.address,
+			instruction: instruction.clone(),
+			cycle_of_instruction: self.cycle_of_instruction,
+			register_before,
+			register_after: self.cpu.register,
+			pixel,
+		};
+
+		if is_final_cycle {
+			self.address += 1;
+			self.cycle_of_instruction = 0;
+		}
+		Some(entry)
+	}
+
+	// steps until the given cycle has completed or the program ends
+	pub fn continue_to(&mut self, cycle: usize) -> Vec<TraceEntry> {
+		let mut entries = vec![];
+		while self.cycle() < cycle {
+			match self.step() {
+				Some(entry) => entries.push(entry),
+				None => break,
+			}
+		}
+		entries
+	}
+
+	pub fn state(&self) -> String {
+		let mut state = format!("cycles completed: {}\nX: {}\n", self.cycle(), self.cpu.register);
+
+		match self.instructions.get(self.address) {
+			Some(instruction) => state.push_str(&format!("next: {:04} {} ({}/{} cycles done)\n",
+				self.address, instruction, self.cycle_of_instruction, instruction.cycles())),
+			None => state.push_str("next: <end of program>\n"),
+		}
+
+		state.push_str("screen so far:\n");
+		for (cycle, register) in self.cpu.register_history.iter().enumerate() {
+			match self.screen.pixel_at(cycle, *register) {
+				Some((column, _, pixel)) => {
+					state.push(pixel);
+					if column == self.screen.width - 1 {
+						state.push('\n');
+					}
+				}
+				None => break,
+			}
+		}
+		if !state.ends_with('\n') {
+			state.push('\n');
+		}
+		state
+	}
+}
+
+const DEBUGGER_HELP: &str = "commands:
+  s, step [n]        run n cycles (default 1)
+  c, continue <n>    run until cycle n has completed
+  p, print           print the cpu state and the screen drawn so far
+  l, list            disassemble the program
+  q, quit            exit the debugger";
+
+pub fn run_interactive(instructions: &[Instruction], screen: &CrtScreen) {
+	let mut stepper = Stepper::new(instructions, screen);
+	let stdin = io::stdin();
+
+	println!("{}", DEBUGGER_HELP);
+	loop {
+		print!("(cycle {}) > ", stepper.cycle());
+		io::stdout().flush().expect("Could not flush stdout");
+
+		let mut line = String::new();
+		match stdin.lock().read_line(&mut line) {
+			Ok(0) => break,
+			Ok(_) => {}
+			Err(err) => panic!("Could not read debugger command. Reason: {}", err),
+		}
+
+		let split = line.split_whitespace().collect::<Vec<&str>>();
+		if split.is_empty() {
+			continue;
+		}
+
+		match split[0] {
+			"s" | "step" => {
+				let count = match split.get(1).map(|count| count.parse::<usize>()) {
+					Some(Ok(count)) => count,
+					Some(Err(_)) => {
+						println!("Could not parse step count '{}'", split[1]);
+						continue;
+					}
+					None => 1,
+				};
+				for entry in stepper.continue_to(stepper.cycle().saturating_add(count)) {
+					println!("{}", entry);
+				}
+			}
+			"c" | "continue" => {
+				let cycle = match split.get(1).map(|cycle| cycle.parse::<usize>()) {
+					Some(Ok(cycle)) => cycle,
+					_ => {
+						println!("Provide the cycle to continue to, e.g. 'continue 20'");
+						continue;
+					}
+				};
+				if let Some(entry) = stepper.continue_to(cycle).last() {
+					println!("{}", entry);
+				}
+			}
+			"p" | "print" => print!("{}", stepper.state()),
+			"l" | "list" => print!("{}", disassemble(instructions)),
+			"q" | "quit" => break,
+			_ => println!("Unknown command '{}'\n{}", split[0], DEBUGGER_HELP),
+		}
+
+		if stepper.is_finished() {
+			println!("Program finished after {} cycles", stepper.cycle());
+		}
+	}
+}
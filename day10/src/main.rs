@@ -2,10 +2,11 @@
 // Day 10
 
 use std::{env, fs};
+mod debugger;
 
 fn main() {
-	// get file path from commandline input
-	let args: Vec<String> = env::args().collect();
+	// get file path from commandline input. Flags (--disassemble, --trace, --debug) may appear anywhere
+	let (flags, args): (Vec<String>, Vec<String>) = env::args().partition(|arg| arg.starts_with("--"));
 	if args.len() < 2 {
 		panic!("Provide the input file's path as a command line parameter");
 	}
@@ -19,18 +20,56 @@ fn main() {
 	};
 
 	let instruction_set = parse_file_contents(file_contents);
+
+	// optional screen geometry: <width> <height> <sprite width>
+	let crt_width = parse_optional_arg(&args, 2, DEFAULT_CRT_WIDTH);
+	let crt_height = parse_optional_arg(&args, 3, DEFAULT_CRT_HEIGHT);
+	let sprite_width = parse_optional_arg(&args, 4, DEFAULT_SPRITE_WIDTH);
+
+	let mut crt_screen = match CrtScreen::new(crt_width, crt_height, sprite_width, DEFAULT_CRT_LIT, DEFAULT_CRT_DIM) {
+		Ok(screen) => screen,
+		Err(err) => panic!("Could not set up CRT screen. Reason: {}", err),
+	};
+
+	for flag in &flags {
+		match flag.as_str() {
+			"--disassemble" => print!("{}", debugger::disassemble(&instruction_set)),
+			"--trace" => debugger::print_trace(&instruction_set, &crt_screen),
+			"--debug" => debugger::run_interactive(&instruction_set, &crt_screen),
+			_ => panic!("Unknown flag '{}'. Expected '--disassemble', '--trace' or '--debug'", flag),
+		}
+	}
+	if !flags.is_empty() {
+		return;
+	}
+
 	let mut cpu = Cpu::new();
 	cpu.run(&instruction_set);
 
-	let mut crt_screen = CrtScreen::new();
-	crt_screen.populate_screen(&cpu.register_history);
+	let sum_of_signal_strengths = match cpu.get_sum_of_signal_strengths() {
+		Ok(sum) => sum,
+		Err(err) => panic!("Could not calculate signal strengths. Reason: {}", err),
+	};
+	if let Err(err) = crt_screen.populate_screen(&cpu.register_history) {
+		panic!("Could not draw CRT screen. Reason: {}", err);
+	}
 
 	println!("################################");
 	println!("#### Advent of Code, Day 10 ####");
 	println!("################################");
-	println!("Sum of signal strengths: {}", cpu.get_sum_of_signal_strengths());
+	println!("Sum of signal strengths: {}", sum_of_signal_strengths);
 	println!("CRT Monitor:\n");
-	println!("{}", crt_screen.to_string());
+	println!("{}", crt_screen);
+}
+
+fn parse_optional_arg(args: &[String], index: usize, default: usize) -> usize {
+	match args.get(index) {
+		Some(arg) => match arg.parse() {
+			Ok(value) => value,
+			Err(err) => panic!("Could not parse command line parameter '{}'. Reason: {}", arg, err),
+		},
+		None => default,
+	}
 }
 
 fn parse_file_contents(contents: String) -> Vec<Instruction> {
@@ -56,6 +95,22 @@ impl Instruction {
 			_ => panic!("Bad instruction found: '{}'", split[0]),
 		}
 	}
+
+	fn cycles(&self) -> usize {
+		match self {
+			Instruction::Noop => 1,
+			Instruction::Addx(_) => 2,
+		}
+	}
+}
+
+impl std::fmt::Display for Instruction {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Instruction::Noop => write!(f, "noop"),
+			Instruction::Addx(value) => write!(f, "addx {}", value),
+		}
+	}
 }
 
 struct Cpu {
@@ -71,84 +126,139 @@ impl Cpu {
 		}
 	}
 
-	fn run(&mut self, instructions: &Vec<Instruction>) {
+	fn run(&mut self, instructions: &[Instruction]) {
         for instruction in instructions {
             self.execute_instruction(instruction);
         }
     }
 
     fn execute_instruction(&mut self, instruction: &Instruction) {
-        match instruction {
-            Instruction::Noop => {
-				self.register_history.push(self.register);		
-            }
-            Instruction::Addx(value) => {
-				// addx instructions take 2 cycles
-				self.register_history.push(self.register);
-				self.register_history.push(self.register);
-				self.register += value;
-            }
-        }
+		let cycles = instruction.cycles();
+		for cycle in 1..=cycles {
+			self.tick(instruction, cycle == cycles);
+		}
     }
 
-	fn get_sum_of_signal_strengths(&self) -> i32 {
-		(self.register_history[19] * 20) + 
-		(self.register_history[59] * 60) + 
-		(self.register_history[99] * 100) + 
-		(self.register_history[139] * 140) + 
-		(self.register_history[179] * 180) + 
-		(self.register_history[219] * 220)
+	// runs a single cycle. The instruction only takes effect at the end of its final cycle
+	fn tick(&mut self, instruction: &Instruction, is_final_cycle: bool) {
+		self.register_history.push(self.register);
+
+		if is_final_cycle {
+			match instruction {
+				Instruction::Noop => {}
+				Instruction::Addx(value) => self.register += value,
+			}
+		}
+	}
+
+	// the register is read during each cycle, so cycle n uses register_history[n - 1]
+	fn get_sum_of_signal_strengths(&self) -> Result<i32, String> {
+		let mut sum = 0;
+		for cycle in SIGNAL_STRENGTH_CYCLES {
+			match self.register_history.get(cycle - 1) {
+				Some(register) => sum += register * cycle as i32,
+				None => return Err(format!(
+					"Program ended after {} cycles, but the signal strength is needed at cycle {}",
+					self.register_history.len(), cycle)),
+			}
+		}
+		Ok(sum)
 	}
 }
 
 
-const CRT_LIT: char = '#';
-const CRT_DIM: char = '.';
-const CRT_WIDTH: usize = 40;
-const CRT_HEIGHT: usize = 6;
-const CRT_TOTAL_PIXELS: usize = CRT_WIDTH * CRT_HEIGHT;
+// cycles whose signal strengths are added up for part 1
+const SIGNAL_STRENGTH_CYCLES: [usize; 6] = [20, 60, 100, 140, 180, 220];
+
+const DEFAULT_CRT_LIT: char = '#';
+const DEFAULT_CRT_DIM: char = '.';
+const DEFAULT_CRT_WIDTH: usize = 40;
+const DEFAULT_CRT_HEIGHT: usize = 6;
+const DEFAULT_SPRITE_WIDTH: usize = 3;
 
 struct CrtScreen {
+	width: usize,
+	height: usize,
+	sprite_width: usize,
+	lit: char,
+	dim: char,
 	display: Vec<char>,
 }
 
 impl CrtScreen {
-	fn new() -> Self {
-		CrtScreen {
-			display: vec![],
+	fn new(width: usize, height: usize, sprite_width: usize, lit: char, dim: char) -> Result<Self, String> {
+		if width == 0 || height == 0 {
+			return Err(format!("CRT dimensions must be non-zero, got {}x{}", width, height));
+		}
+		if sprite_width == 0 {
+			return Err(String::from("Sprite width must be non-zero"));
 		}
+
+		Ok(CrtScreen {
+			width,
+			height,
+			sprite_width,
+			lit,
+			dim,
+			display: vec![],
+		})
 	}
 
-	fn populate_screen(&mut self, register_history: &Vec<i32>) {
-		for cycle in 0..CRT_TOTAL_PIXELS {
-			let pos_in_row = (cycle % CRT_WIDTH) as i32;
+	fn total_pixels(&self) -> usize {
+		self.width * self.height
+	}
 
-			if ((register_history[cycle] - 1)..=(register_history[cycle] + 1)).contains(&pos_in_row){
-				self.display.push(CRT_LIT);
-			}
-			else {
-				self.display.push(CRT_DIM);
+	// the sprite is centered on the register value. Even widths lean to the right
+	fn sprite_covers(&self, register: i32, pos_in_row: i32) -> bool {
+		let left = register - ((self.sprite_width as i32 - 1) / 2);
+		let right = left + self.sprite_width as i32 - 1;
+		(left..=right).contains(&pos_in_row)
+	}
+
+	fn populate_screen(&mut self, register_history: &[i32]) -> Result<(), String> {
+		let total_pixels = self.total_pixels();
+		if register_history.len() < total_pixels {
+			return Err(format!(
+				"Program ended after {} cycles, but the {}x{} screen needs {} cycles to be drawn",
+				register_history.len(), self.width, self.height, total_pixels));
+		}
+
+		self.display.clear();
+		for (cycle, register) in register_history.iter().enumerate() {
+			match self.pixel_at(cycle, *register) {
+				Some((_, _, pixel)) => self.display.push(pixel),
+				None => break,
 			}
 		}
+		Ok(())
 	}
 
-	fn to_string(&self) -> String {
-		if self.display.len() < CRT_TOTAL_PIXELS {
-			panic!("Screen has not been fully populated yet. Only {} pixels exist, expected {}", self.display.len(), CRT_TOTAL_PIXELS);
+	// returns the (column, row, glyph) drawn during the zero-based cycle, or None once the screen is full
+	fn pixel_at(&self, cycle: usize, register: i32) -> Option<(usize, usize, char)> {
+		if cycle >= self.total_pixels() {
+			return None;
 		}
 
-		let mut screen = String::new();
-		let mut curr_pixel = 1;
+		let column = cycle % self.width;
+		let row = cycle / self.width;
+		if self.sprite_covers(register, column as i32) {
+			Some((column, row, self.lit))
+		}
+		else {
+			Some((column, row, self.dim))
+		}
+	}
+}
 
-		for pixel in &self.display {
-			screen.push(*pixel);
+impl std::fmt::Display for CrtScreen {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		if self.display.len() < self.total_pixels() {
+			panic!("Screen has not been fully populated yet. Only {} pixels exist, expected {}", self.display.len(), self.total_pixels());
+		}
 
-			if curr_pixel % CRT_WIDTH == 0 {
-				screen.push('\n');
-			}
-			
-			curr_pixel += 1;
+		for row in self.display.chunks(self.width) {
+			writeln!(f, "{}", row.iter().collect::<String>())?;
 		}
-		screen
+		Ok(())
 	}
 }
@@ -2,16 +2,24 @@
 // Day 12
 //
 // Notes: This code is pretty sloppy, so read at your own risk.
-//  Part 1 is solved by using a breadth-first search. Starting at the S, check all neighbors, then check the neighbor's neighbors, etc until
-//    the end is found.
-//  My solution to part 2 of this puzzle is extremely unoptimized. I essentially do the same breadth-first search starting from EVERY 'a' on the map.
-//    This was 1338 of them on my map, so this took a while to run. I know a faster approach would be to do the BFS starting from the end-point, but I
-//    already spent enough time on this day's puzzle and wanted to move on...
-
-use std::{cmp::min, collections::VecDeque, env, fs};
+//  Both parts are solved by a single breadth-first search that starts at the E and walks the climb rule backwards
+//    (a step from 'here' to 'there' is only allowed if 'there' could have climbed up to 'here').
+//    That gives every cell's distance to the end in one pass, so part 1 is the distance at S and part 2 is the
+//    smallest distance at any 'a'.
+//  The first version of part 2 ran a forwards BFS from EVERY 'a' on the map (1338 of them on my map), which was super duper slow.
+//  weighted_search() is an A*/Dijkstra over the same map with pluggable climb rules and step costs. Run with --weighted
+//    to compare a few of them, and --show-routes to draw the routes that were found.
+
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, VecDeque},
+    env, fs,
+};
 
 const START_CHAR: char = 'S';
 const END_CHAR: char = 'E';
+const LOWEST_ELEVATION: u8 = 1;
+const HIGHEST_ELEVATION: u8 = 26;
 
 fn main() {
     // get file path from commandline input
@@ -28,47 +36,99 @@ fn main() {
         Err(err) => panic!("Could not open input file {}. Reason: {}", input_file, err),
     };
 
-    // Get part 1 answer
-    let mut height_map_part_1 = parse_file_to_height_map(&file_contents);
-    height_map_part_1.explore_until_end(height_map_part_1.start_x, height_map_part_1.start_y);
-
-    // Get part 2 answer (super duper slow)
-    let mut height_map_part_2 = parse_file_to_height_map(&file_contents);
-    let mut part_2_shortest = i32::MAX;
-    for y in 0..height_map_part_2.height() {
-        for x in 0..height_map_part_2.width() {
-            if height_map_part_2.elevation_at_x_y(x, y) == 1 {
-                height_map_part_2.explore_until_end(x, y);
-                part_2_shortest = min(part_2_shortest, height_map_part_2.distance_to_end());
-            }
-        }
-    }
+    let show_routes = args.iter().any(|arg| arg == "--show-routes");
+    let show_weighted = args.iter().any(|arg| arg == "--weighted");
+
+    // one reverse search from E answers both parts
+    let mut height_map = parse_file_to_height_map(&file_contents);
+    height_map.explore_from_end();
+    let part_1_route = height_map.route_to_end(height_map.start_x, height_map.start_y);
+    let part_2_route = height_map
+        .closest_cell_with_elevation(LOWEST_ELEVATION)
+        .and_then(|(x, y)| height_map.route_to_end(x, y));
 
     println!("################################");
     println!("#### Advent of Code, Day 12 ####");
     println!("################################");
     println!(
-        "Quickest path from S to E is {} moves",
-        height_map_part_1.distance_to_end()
+        "Quickest path from S to E is {}",
+        describe_route(&part_1_route)
     );
     println!(
-        "Shortest path from any low-point to E is {} moves",
-        part_2_shortest
+        "Shortest path from any low-point to E is {}",
+        describe_route(&part_2_route)
     );
+
+    if show_routes {
+        for route in [&part_1_route, &part_2_route].into_iter().flatten() {
+            println!("\n{}", height_map.route_to_string(route));
+        }
+    }
+
+    if show_weighted {
+        let start = (height_map.start_x, height_map.start_y);
+        let end = (height_map.end_x, height_map.end_y);
+
+        // with unit costs and no heuristic this must agree with the BFS above
+        let dijkstra = height_map.weighted_search(start, end, can_climb, unit_step_cost, |_| 0);
+        println!("Dijkstra with unit step costs from S to E: {}", describe_weighted_route(&dijkstra));
+
+        let a_star = height_map.weighted_search(
+            start,
+            end,
+            can_climb_at_most(2),
+            height_change_step_cost,
+            manhattan_heuristic(end),
+        );
+        println!(
+            "A* climbing at most 2 with cost per height change from S to E: {}",
+            describe_weighted_route(&a_star)
+        );
+
+        let uphill_penalty = height_map.weighted_search(
+            start,
+            end,
+            can_climb,
+            climb_penalty_step_cost,
+            manhattan_heuristic(end),
+        );
+        println!(
+            "A* with a penalty for climbing from S to E: {}",
+            describe_weighted_route(&uphill_penalty)
+        );
+
+        if show_routes {
+            for (_, route) in [&a_star, &uphill_penalty].into_iter().flatten() {
+                println!("\n{}", height_map.route_to_string(route));
+            }
+        }
+    }
+}
+
+fn describe_route(route: &Option<Vec<(usize, usize)>>) -> String {
+    match route {
+        Some(route) => format!("{} moves", route.len() - 1),
+        None => String::from("unreachable"),
+    }
+}
+
+fn describe_weighted_route(result: &Option<(u32, Vec<(usize, usize)>)>) -> String {
+    match result {
+        Some((cost, route)) => format!("cost {} over {} moves", cost, route.len() - 1),
+        None => String::from("unreachable"),
+    }
 }
 
-fn parse_file_to_height_map(contents: &String) -> HeightMap {
+fn parse_file_to_height_map(contents: &str) -> HeightMap {
     let mut map: Vec<Vec<u8>> = vec![];
     let mut start_x = 0;
     let mut start_y = 0;
     let mut end_x = 0;
     let mut end_y = 0;
 
-    let mut y = 0;
-    for line in contents.lines() {
-        let mut x = 0;
+    for (y, line) in contents.lines().enumerate() {
         let mut row: Vec<u8> = vec![];
-        for c in line.chars() {
+        for (x, c) in line.chars().enumerate() {
             if c == START_CHAR {
                 start_x = x;
                 start_y = y;
@@ -78,10 +138,8 @@ fn parse_file_to_height_map(contents: &String) -> HeightMap {
                 end_y = y;
             }
             row.push(parse_char_to_height(c));
-            x += 1;
         }
         map.push(row);
-        y += 1;
     }
 
     HeightMap::new(map, start_x, start_y, end_x, end_y)
@@ -91,54 +149,101 @@ fn parse_file_to_height_map(contents: &String) -> HeightMap {
 // Start 'S' is equal to 'a' and End 'E' is equal to 'z'
 // panics if not converted correctly (invalid char)
 fn parse_char_to_height(c: char) -> u8 {
-    let height: u8 = match c {
-        'S' => 1,
-        'E' => 26,
-        _ => c as u8 - 96,
-    };
-
-    if height <= 0 || height >= 27 {
-        panic!("encountered an invalid char: '{}'.", c);
+    match c {
+        'S' => LOWEST_ELEVATION,
+        'E' => HIGHEST_ELEVATION,
+        'a'..='z' => c as u8 - 96,
+        _ => panic!("encountered an invalid char: '{}'.", c),
     }
-
-    height
 }
 
+#[allow(dead_code)]
 fn parse_height_to_char(h: u8) -> char {
     (h + 96) as char
 }
 
+// the puzzle's climb rule: you can step down any amount, but only up by one
+fn can_climb(from: u8, to: u8) -> bool {
+    to <= from + 1
+}
+
+// climb rule for terrain where a single step can gain up to max_ascent
+fn can_climb_at_most(max_ascent: u8) -> impl Fn(u8, u8) -> bool {
+    move |from, to| to <= from.saturating_add(max_ascent)
+}
+
+// every step costs the same, which makes a weighted search find the same distances as the BFS
+fn unit_step_cost(_from: u8, _to: u8) -> u32 {
+    1
+}
+
+// a flat step costs 1, plus 1 for every level climbed or descended
+fn height_change_step_cost(from: u8, to: u8) -> u32 {
+    1 + from.abs_diff(to) as u32
+}
+
+// a flat or downhill step costs 1, plus 2 for every level climbed
+fn climb_penalty_step_cost(from: u8, to: u8) -> u32 {
+    1 + 2 * to.saturating_sub(from) as u32
+}
+
+// never overestimates as long as every step costs at least 1
+fn manhattan_heuristic(goal: (usize, usize)) -> impl Fn((usize, usize)) -> u32 {
+    move |(x, y)| (x.abs_diff(goal.0) + y.abs_diff(goal.1)) as u32
+}
+
+// fixed-size set of grid cells, one bit per cell
+struct VisitedSet {
+    width: usize,
+    bits: Vec<u64>,
+}
+
+impl VisitedSet {
+    fn new(width: usize, height: usize) -> Self {
+        VisitedSet {
+            width,
+            bits: vec![0; (width * height).div_ceil(64)],
+        }
+    }
+
+    // marks the cell as visited, returning false if it already was
+    fn insert(&mut self, x: usize, y: usize) -> bool {
+        let index = y * self.width + x;
+        let mask = 1 << (index % 64);
+        let word = &mut self.bits[index / 64];
+        let newly_inserted = *word & mask == 0;
+        *word |= mask;
+        newly_inserted
+    }
+}
+
 struct HeightMap {
     map: Vec<Vec<u8>>,
     start_x: usize,
     start_y: usize,
     end_x: usize,
     end_y: usize,
-    distances: Vec<Vec<i32>>,
+    distances: Vec<Vec<Option<usize>>>,
+    // the neighbor each cell was discovered from, i.e. one step closer to the search's source
+    parents: Vec<Vec<Option<(usize, usize)>>>,
 }
 
 impl HeightMap {
     fn new(map: Vec<Vec<u8>>, start_x: usize, start_y: usize, end_x: usize, end_y: usize) -> Self {
-        // build distances map with every entry having -1
-        let mut distances = vec![];
-        for _ in 0..map.len() {
-            let mut distance_row: Vec<i32> = vec![];
-            for _ in 0..map[0].len() {
-                distance_row.push(-1);
-            }
-            distances.push(distance_row);
-        }
+        // every cell starts out unexplored
+        let width = map.first().map_or(0, |row| row.len());
+        let distances = vec![vec![None; width]; map.len()];
+        let parents = vec![vec![None; width]; map.len()];
 
-        let mut ret = HeightMap {
+        HeightMap {
             map,
             start_x,
             start_y,
             end_x,
             end_y,
-            distances: distances,
-        };
-        ret.set_distance(start_x, start_y, 0);
-        ret
+            distances,
+            parents,
+        }
     }
 
     fn height(&self) -> usize {
@@ -156,134 +261,215 @@ impl HeightMap {
         self.map[y][x]
     }
 
-    fn distance_at_x_y(&self, x: usize, y: usize) -> i32 {
+    fn distance_at_x_y(&self, x: usize, y: usize) -> Option<usize> {
         self.distances[y][x]
     }
 
-    fn distance_to_end(&self) -> i32 {
-        self.distance_at_x_y(self.end_x, self.end_y)
-    }
-
-    fn set_distance(&mut self, x: usize, y: usize, distance: i32) {
-        self.distances[y][x] = distance;
+    fn set_distance(&mut self, x: usize, y: usize, distance: usize) {
+        self.distances[y][x] = Some(distance);
     }
 
-    fn find_neighbors(&mut self, x: usize, y: usize) -> Vec<(usize, usize)> {
-        let mut new_neighbors = vec![];
-        // up
-        if let Some((new_x, new_y)) = self.get_neighbor_up(x, y) {
-            new_neighbors.push((new_x, new_y));
+    // orthogonal neighbors of (x, y) that can be stepped onto according to can_step(from_elevation, to_elevation)
+    fn find_neighbors(&self, x: usize, y: usize, can_step: &impl Fn(u8, u8) -> bool) -> Vec<(usize, usize)> {
+        let mut candidates = vec![];
+        if y > 0 {
+            candidates.push((x, y - 1));
         }
-
-        // down
-        if let Some((new_x, new_y)) = self.get_neighbor_down(x, y) {
-            new_neighbors.push((new_x, new_y));
+        if y + 1 < self.height() {
+            candidates.push((x, y + 1));
         }
-
-        // left
-        if let Some((new_x, new_y)) = self.get_neighbor_left(x, y) {
-            new_neighbors.push((new_x, new_y));
+        if x > 0 {
+            candidates.push((x - 1, y));
         }
-
-        // right
-        if let Some((new_x, new_y)) = self.get_neighbor_right(x, y) {
-            new_neighbors.push((new_x, new_y));
+        if x + 1 < self.width() {
+            candidates.push((x + 1, y));
         }
 
-        new_neighbors
+        let elevation = self.elevation_at_x_y(x, y);
+        candidates
+            .into_iter()
+            .filter(|&(new_x, new_y)| can_step(elevation, self.elevation_at_x_y(new_x, new_y)))
+            .collect()
     }
 
-    fn get_neighbor_up(&self, x: usize, y: usize) -> Option<(usize, usize)> {
-        if y == 0 {
-            return Option::None;
-        } else if self.elevation_at_x_y(x, y - 1) <= self.elevation_at_x_y(x, y) + 1 {
-            return Option::Some((x, y - 1));
-        } else {
-            return Option::None;
+    // multi-source breadth-first search. Afterwards every cell holds its distance to the nearest source
+    // and the neighbor it was reached from, or None if no source can reach it
+    fn breadth_first_search(&mut self, sources: &[(usize, usize)], can_step: impl Fn(u8, u8) -> bool) {
+        self.distances = vec![vec![None; self.width()]; self.height()];
+        self.parents = vec![vec![None; self.width()]; self.height()];
+        let mut explored = VisitedSet::new(self.width(), self.height());
+        let mut queue_to_check = VecDeque::<(usize, usize)>::new();
+
+        for &(x, y) in sources {
+            if explored.insert(x, y) {
+                self.set_distance(x, y, 0);
+                queue_to_check.push_back((x, y));
+            }
         }
-    }
 
-    fn get_neighbor_down(&self, x: usize, y: usize) -> Option<(usize, usize)> {
-        if y == self.height() - 1 {
-            return Option::None;
-        } else if self.elevation_at_x_y(x, y + 1) <= self.elevation_at_x_y(x, y) + 1 {
-            return Option::Some((x, y + 1));
-        } else {
-            return Option::None;
+        while let Some((curr_x, curr_y)) = queue_to_check.pop_front() {
+            let next_dist = self.distances[curr_y][curr_x].unwrap() + 1;
+
+            for (n_x, n_y) in self.find_neighbors(curr_x, curr_y, &can_step) {
+                if explored.insert(n_x, n_y) {
+                    self.set_distance(n_x, n_y, next_dist);
+                    self.parents[n_y][n_x] = Some((curr_x, curr_y));
+                    queue_to_check.push_back((n_x, n_y));
+                }
+            }
         }
     }
 
-    fn get_neighbor_left(&self, x: usize, y: usize) -> Option<(usize, usize)> {
-        if x == 0 {
-            return Option::None;
-        } else if self.elevation_at_x_y(x - 1, y) <= self.elevation_at_x_y(x, y) + 1 {
-            return Option::Some((x - 1, y));
-        } else {
-            return Option::None;
+    // fills in every cell's distance to E by walking the climb rule backwards from E
+    fn explore_from_end(&mut self) {
+        self.breadth_first_search(&[(self.end_x, self.end_y)], |from, to| can_climb(to, from));
+    }
+
+    // the reached cell with the given elevation that is closest to the search's source, if any were reached
+    fn closest_cell_with_elevation(&self, elevation: u8) -> Option<(usize, usize)> {
+        let mut closest: Option<((usize, usize), usize)> = None;
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                if self.elevation_at_x_y(x, y) != elevation {
+                    continue;
+                }
+                if let Some(distance) = self.distance_at_x_y(x, y) {
+                    if closest.is_none_or(|(_, shortest)| distance < shortest) {
+                        closest = Some(((x, y), distance));
+                    }
+                }
+            }
         }
+        closest.map(|(cell, _)| cell)
     }
 
-    fn get_neighbor_right(&self, x: usize, y: usize) -> Option<(usize, usize)> {
-        if x == self.width() - 1 {
-            return Option::None;
-        } else if self.elevation_at_x_y(x + 1, y) <= self.elevation_at_x_y(x, y) + 1 {
-            return Option::Some((x + 1, y));
-        } else {
-            return Option::None;
+    // follows the parents from (x, y) back to the search's source. After explore_from_end() this is
+    // the route from (x, y) to E, including both ends. None if (x, y) was never reached
+    fn route_to_source(&self, x: usize, y: usize) -> Option<Vec<(usize, usize)>> {
+        self.distance_at_x_y(x, y)?;
+
+        let mut route = vec![(x, y)];
+        let mut curr = (x, y);
+        while let Some(parent) = self.parents[curr.1][curr.0] {
+            route.push(parent);
+            curr = parent;
         }
+        Some(route)
     }
 
-    fn explore_until_end(&mut self, starting_x: usize, starting_y: usize) {
-        // queue_to_check holds ((x, y), prev_neighbor_distance)
-        let mut queue_to_check = VecDeque::<((usize, usize), i32)>::new();
-        let mut explored = Vec::<(usize, usize)>::new();
-
-        // handle starting location
-        self.set_distance(starting_x, starting_y, 0);
-        explored.push((starting_x, starting_y));
-        for n in self.find_neighbors(starting_x, starting_y) {
-            queue_to_check.push_back((n, 0));
-            explored.push(n);
+    fn route_to_end(&self, x: usize, y: usize) -> Option<Vec<(usize, usize)>> {
+        let route = self.route_to_source(x, y)?;
+        match route.last() {
+            Some(&(last_x, last_y)) if last_x == self.end_x && last_y == self.end_y => Some(route),
+            _ => None,
         }
+    }
 
-        while queue_to_check.len() > 0 {
-            let curr = queue_to_check.pop_front().unwrap();
-            let curr_x = curr.0 .0;
-            let curr_y = curr.0 .1;
-            let last_dist = curr.1;
-            self.set_distance(curr_x, curr_y, last_dist + 1);
+    // A* from start to goal. Steps must satisfy can_step(from_elevation, to_elevation) and cost
+    // step_cost(from_elevation, to_elevation). A heuristic of |_| 0 turns this into Dijkstra's algorithm.
+    // Returns the total cost and the route including both ends, or None if the goal can't be reached
+    fn weighted_search(
+        &self,
+        start: (usize, usize),
+        goal: (usize, usize),
+        can_step: impl Fn(u8, u8) -> bool,
+        step_cost: impl Fn(u8, u8) -> u32,
+        heuristic: impl Fn((usize, usize)) -> u32,
+    ) -> Option<(u32, Vec<(usize, usize)>)> {
+        let mut costs: Vec<Vec<Option<u32>>> = vec![vec![None; self.width()]; self.height()];
+        let mut parents: Vec<Vec<Option<(usize, usize)>>> = vec![vec![None; self.width()]; self.height()];
+        let mut settled = VisitedSet::new(self.width(), self.height());
+
+        // queue_to_check holds (cost so far + heuristic, cost so far, (x, y)), cheapest first
+        let mut queue_to_check = BinaryHeap::new();
+        costs[start.1][start.0] = Some(0);
+        queue_to_check.push(Reverse((heuristic(start), 0, start)));
+
+        while let Some(Reverse((_, cost, (curr_x, curr_y)))) = queue_to_check.pop() {
+            // skip stale queue entries for cells that were already reached more cheaply
+            if !settled.insert(curr_x, curr_y) {
+                continue;
+            }
 
-            // end early if we reached the end
-            if curr_x == self.end_x && curr_y == self.end_y {
-                break;
+            if (curr_x, curr_y) == goal {
+                let mut route = vec![goal];
+                let mut curr = goal;
+                while let Some(parent) = parents[curr.1][curr.0] {
+                    route.push(parent);
+                    curr = parent;
+                }
+                route.reverse();
+                return Some((cost, route));
             }
 
-            for n in self.find_neighbors(curr_x, curr_y) {
-                if !explored.contains(&n) {
-                    queue_to_check.push_back((n, last_dist + 1));
-                    explored.push(n);
+            let elevation = self.elevation_at_x_y(curr_x, curr_y);
+            for (n_x, n_y) in self.find_neighbors(curr_x, curr_y, &can_step) {
+                let new_cost = cost + step_cost(elevation, self.elevation_at_x_y(n_x, n_y));
+                if costs[n_y][n_x].is_none_or(|old_cost| new_cost < old_cost) {
+                    costs[n_y][n_x] = Some(new_cost);
+                    parents[n_y][n_x] = Some((curr_x, curr_y));
+                    queue_to_check.push(Reverse((new_cost + heuristic((n_x, n_y)), new_cost, (n_x, n_y))));
                 }
             }
         }
+        None
     }
 
-    fn height_map_to_string(&self) -> String {
+    fn marker_at_x_y(&self, x: usize, y: usize) -> Option<char> {
+        if x == self.start_x && y == self.start_y {
+            Some(START_CHAR)
+        } else if x == self.end_x && y == self.end_y {
+            Some(END_CHAR)
+        } else {
+            None
+        }
+    }
+
+    // draws the map one char per cell
+    fn map_to_string(&self, cell_to_char: impl Fn(usize, usize) -> char) -> String {
         let mut s = String::new();
         for y in 0..self.height() {
             for x in 0..self.width() {
-                if x == self.start_x && y == self.start_y {
-                    s.push(START_CHAR);
-                } else if x == self.end_x && y == self.end_y {
-                    s.push(END_CHAR);
-                } else {
-                    s.push(parse_height_to_char(self.elevation_at_x_y(x, y)));
-                }
+                s.push(cell_to_char(x, y));
             }
             s += "\n";
         }
         s
     }
 
+    #[allow(dead_code)]
+    fn height_map_to_string(&self) -> String {
+        self.map_to_string(|x, y| {
+            self.marker_at_x_y(x, y)
+                .unwrap_or_else(|| parse_height_to_char(self.elevation_at_x_y(x, y)))
+        })
+    }
+
+    // draws the route like the puzzle text does: each step is an arrow pointing to the next cell
+    // and every cell off the route is a '.'
+    fn route_to_string(&self, route: &[(usize, usize)]) -> String {
+        let mut arrows = vec![vec![None; self.width()]; self.height()];
+        for step in route.windows(2) {
+            let ((x, y), (next_x, next_y)) = (step[0], step[1]);
+            arrows[y][x] = Some(if next_x > x {
+                '>'
+            } else if next_x < x {
+                '<'
+            } else if next_y < y {
+                '^'
+            } else {
+                'v'
+            });
+        }
+
+        self.map_to_string(|x, y| {
+            arrows[y][x]
+                .or_else(|| self.marker_at_x_y(x, y))
+                .unwrap_or('.')
+        })
+    }
+
+    #[allow(dead_code)]
     fn distance_map_to_string(&self) -> String {
         let mut s = String::new();
         for y in 0..self.height() {
@@ -293,7 +479,10 @@ impl HeightMap {
                 // } else if x == self.end_x && y == self.end_y {
                 //     s += &format!("{: >5}", END_CHAR);
                 // } else {
-                s += &format!("{: >5}", &self.distance_at_x_y(x, y))
+                match self.distance_at_x_y(x, y) {
+                    Some(distance) => s += &format!("{: >5}", distance),
+                    None => s += &format!("{: >5}", '.'),
+                }
                 // }
             }
             s += "\n";
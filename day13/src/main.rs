@@ -4,85 +4,293 @@
 // Notes: This was a fun one. I need some inspiration for parsing the input into packets. I used a guide on Github from jcollard (https://github.com/jcollard/AdventOfCode2022/tree/main/Day13-Guide)
 //	This puzzle was was a great way to demonstrate how powerful rust's enums are.
 
-use std::{collections::VecDeque, env, fs};
+use serde_json::Value as JsonValue;
+use std::{cmp::Ordering, env, fs};
 
-#[derive(PartialEq)]
 enum Packet {
     Value(i32),
     List(Vec<Packet>),
 }
 
 impl Packet {
-    fn parse(input: &String) -> Self {
-        let mut data: VecDeque<char> = input.chars().collect();
-        Self::parse_list(&mut data)
+    // parses a single packet from one line. Surrounding whitespace (including a '\r') is ignored
+    fn parse(input: &str) -> Result<Self, ParseError> {
+        let mut parser = PacketParser::new(input);
+        parser.skip_whitespace();
+        let packet = parser.parse_list()?;
+        parser.skip_whitespace();
+        match parser.peek() {
+            None => Ok(packet),
+            Some(c) => Err(parser.error(format!("Expected end of packet, but found '{}'", c))),
+        }
     }
 
-    fn parse_list(data: &mut VecDeque<char>) -> Packet {
-        let mut list: Vec<Packet> = vec![];
+    // one element per line, indented by nesting depth. Lists without nested lists stay on one line
+    fn to_pretty_string(&self) -> String {
+        let mut s = String::new();
+        self.write_pretty(&mut s, 0);
+        s
+    }
 
-        // remove leading '['
-        data.pop_front();
+    fn write_pretty(&self, s: &mut String, depth: usize) {
+        let list = match self {
+            Packet::List(list) if list.iter().any(|p| matches!(p, Packet::List(_))) => list,
+            _ => {
+                *s += &self.to_string();
+                return;
+            }
+        };
 
-        while data.front().unwrap() != &']' {
-            if data.front().unwrap() == &',' {
-                data.pop_front();
+        *s += "[\n";
+        for (i, element) in list.iter().enumerate() {
+            *s += &PRETTY_INDENT.repeat(depth + 1);
+            element.write_pretty(s, depth + 1);
+            if i < list.len() - 1 {
+                s.push(',');
             }
-            list.push(Self::parse_element(data));
+            s.push('\n');
         }
-
-        // remove ']' from list
-        data.pop_front();
-        Packet::List(list)
+        *s += &PRETTY_INDENT.repeat(depth);
+        s.push(']');
     }
+}
 
-    fn parse_element(data: &mut VecDeque<char>) -> Packet {
-        let next = data.front().unwrap();
-        if next.is_ascii_digit() {
-            return Self::parse_int(data);
-        } else if next == &'[' {
-            return Self::parse_list(data);
-        } else {
-            panic!(
-                "Error parsing packet element. Expected digit or '[', but found {:?}",
-                data
-            );
+const PRETTY_INDENT: &str = "  ";
+
+// packets are ordered by the puzzle's rules: integers compare numerically, lists compare element by element
+// (a shorter list that runs out first is smaller), and an integer compared to a list is treated as [integer]
+impl Ord for Packet {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Packet::Value(l), Packet::Value(r)) => l.cmp(r),
+            (Packet::List(l), Packet::List(r)) => l.cmp(r),
+            (Packet::Value(_), Packet::List(r)) => std::slice::from_ref(self).cmp(r),
+            (Packet::List(l), Packet::Value(_)) => l.as_slice().cmp(std::slice::from_ref(other)),
         }
     }
+}
 
-    fn parse_int(data: &mut VecDeque<char>) -> Packet {
-        let mut token = String::new();
-        while data.front().unwrap().is_ascii_digit() {
-            token.push(data.pop_front().unwrap());
-        }
+impl PartialOrd for Packet {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
 
-        Packet::Value(token.parse().unwrap())
+// equality has to agree with the ordering, so 2 == [2] == [[2]]
+impl PartialEq for Packet {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
     }
+}
 
-    // attempts to recreate the input packet (commas are a bit messed up, but close enough...)
-    fn to_string(&self) -> String {
-        let mut s = String::new();
+impl Eq for Packet {}
+
+// writes the packet back out in the same syntax as the input, e.g. [1,[2,3],[]]
+impl std::fmt::Display for Packet {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            Packet::Value(val) => {
-                s += &val.to_string();
-                s += ","
-            }
+            Packet::Value(val) => write!(f, "{}", val),
             Packet::List(list) => {
-                s += "[";
-                for element in list {
-                    s += &element.to_string();
+                write!(f, "[")?;
+                for (i, element) in list.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", element)?;
                 }
-                s += "],";
+                write!(f, "]")
             }
         }
+    }
+}
 
-        s
+// packets are already valid JSON: lists become arrays and values become numbers
+impl From<&Packet> for JsonValue {
+    fn from(packet: &Packet) -> Self {
+        match packet {
+            Packet::Value(val) => JsonValue::from(*val),
+            Packet::List(list) => JsonValue::Array(list.iter().map(JsonValue::from).collect()),
+        }
     }
 }
 
-impl std::fmt::Display for Packet {
+// reads any JSON array of (nested arrays of) integers as a packet
+impl TryFrom<&JsonValue> for Packet {
+    type Error = String;
+
+    fn try_from(json: &JsonValue) -> Result<Self, Self::Error> {
+        Self::from_json(json, "$")
+    }
+}
+
+impl Packet {
+    fn from_json(json: &JsonValue, path: &str) -> Result<Packet, String> {
+        match json {
+            JsonValue::Array(_) => Self::from_json_element(json, path),
+            _ => Err(format!("{} is {}, but a packet must be a JSON array", path, describe_json(json))),
+        }
+    }
+
+    // path is where the element sits in the JSON document, e.g. $[1][0], so errors can point at it
+    fn from_json_element(json: &JsonValue, path: &str) -> Result<Packet, String> {
+        match json {
+            JsonValue::Array(elements) => {
+                let mut list = vec![];
+                for (i, element) in elements.iter().enumerate() {
+                    list.push(Self::from_json_element(element, &format!("{}[{}]", path, i))?);
+                }
+                Ok(Packet::List(list))
+            }
+            JsonValue::Number(number) => match number.as_i64().map(i32::try_from) {
+                Some(Ok(val)) => Ok(Packet::Value(val)),
+                _ => Err(format!(
+                    "{} is {}, but packet values must be integers that fit in an i32",
+                    path,
+                    describe_json(json)
+                )),
+            },
+            _ => Err(format!(
+                "{} is {}, but packets can only hold integers and arrays",
+                path,
+                describe_json(json)
+            )),
+        }
+    }
+}
+
+fn describe_json(json: &JsonValue) -> String {
+    match json {
+        JsonValue::Null => String::from("null"),
+        JsonValue::Bool(val) => format!("the boolean {}", val),
+        JsonValue::Number(val) => format!("the number {}", val),
+        JsonValue::String(val) => format!("the string {:?}", val),
+        JsonValue::Array(_) => String::from("an array"),
+        JsonValue::Object(_) => String::from("an object"),
+    }
+}
+
+// points at the exact character a packet stopped making sense
+struct ParseError {
+    line_number: usize,
+    column: usize,
+    line: String,
+    message: String,
+}
+
+impl std::fmt::Display for ParseError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}", self.to_string())
+        writeln!(f, "line {}, column {}: {}", self.line_number, self.column, self.message)?;
+        writeln!(f, "  {}", self.line)?;
+        write!(f, "  {}^", " ".repeat(self.column - 1))
+    }
+}
+
+// recursive descent parser over the characters of one line
+struct PacketParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl PacketParser {
+    fn new(input: &str) -> Self {
+        PacketParser {
+            chars: input.chars().collect(),
+            pos: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn error(&self, message: String) -> ParseError {
+        ParseError {
+            line_number: 1,
+            column: self.pos + 1,
+            line: self.chars.iter().collect::<String>().trim_end().to_string(),
+            message,
+        }
+    }
+
+    fn describe_next(&self) -> String {
+        match self.peek() {
+            Some(c) => format!("'{}'", c),
+            None => String::from("end of line"),
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(format!("Expected '{}', but found {}", expected, self.describe_next())))
+        }
+    }
+
+    fn parse_list(&mut self) -> Result<Packet, ParseError> {
+        let mut list: Vec<Packet> = vec![];
+        self.expect('[')?;
+
+        if self.peek() == Some(']') {
+            self.pos += 1;
+            return Ok(Packet::List(list));
+        }
+
+        loop {
+            list.push(self.parse_element()?);
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {
+                    self.pos += 1;
+                    return Ok(Packet::List(list));
+                }
+                _ => {
+                    return Err(self.error(format!("Expected ',' or ']', but found {}", self.describe_next())))
+                }
+            }
+        }
+    }
+
+    fn parse_element(&mut self) -> Result<Packet, ParseError> {
+        match self.peek() {
+            Some('[') => self.parse_list(),
+            Some(c) if c.is_ascii_digit() || c == '-' => self.parse_int(),
+            _ => Err(self.error(format!(
+                "Expected a number or '[', but found {}",
+                self.describe_next()
+            ))),
+        }
+    }
+
+    fn parse_int(&mut self) -> Result<Packet, ParseError> {
+        let token_start = self.pos;
+        let mut token = String::new();
+        if self.peek() == Some('-') {
+            token.push('-');
+            self.pos += 1;
+        }
+        if !self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            return Err(self.error(format!("Expected a digit, but found {}", self.describe_next())));
+        }
+        while let Some(c) = self.peek().filter(|c| c.is_ascii_digit()) {
+            token.push(c);
+            self.pos += 1;
+        }
+
+        match token.parse() {
+            Ok(value) => Ok(Packet::Value(value)),
+            Err(err) => {
+                self.pos = token_start;
+                Err(self.error(format!("Could not read number {}. Reason: {}", token, err)))
+            }
+        }
     }
 }
 
@@ -112,16 +320,42 @@ fn main() {
         Err(err) => panic!("Could not open input file {}. Reason: {}", input_file, err),
     };
 
-    let packet_pairs = parse_file(&file_contents);
+    // --from-json reads the input as one JSON array of packets, where each two packets in a row make a pair
+    let packet_pairs = if args.iter().any(|arg| arg == "--from-json") {
+        match parse_json_file(&file_contents) {
+            Ok(pairs) => pairs,
+            Err(err) => panic!("Could not parse JSON input file {}. Reason: {}", input_file, err),
+        }
+    } else {
+        match parse_file(&file_contents) {
+            Ok(pairs) => pairs,
+            Err(err) => panic!("Could not parse input file {}. Reason: {}", input_file, err),
+        }
+    };
+
+    if args.iter().any(|arg| arg == "--to-json") {
+        let json = JsonValue::Array(
+            packet_pairs
+                .iter()
+                .flat_map(|pair| [JsonValue::from(&pair.p1), JsonValue::from(&pair.p2)])
+                .collect(),
+        );
+        println!("{}", json);
+    }
+
+    if args.iter().any(|arg| arg == "--pretty") {
+        for (index, pair) in packet_pairs.iter().enumerate() {
+            println!("== Pair {} ==", index + 1);
+            println!("{}\n{}\n", pair.p1.to_pretty_string(), pair.p2.to_pretty_string());
+        }
+    }
 
     // part 1
     let mut sum = 0;
-    let mut index = 1;
-    for pair in &packet_pairs {
-        if is_in_order(&pair.p1, &pair.p2) {
-            sum += index;
+    for (index, pair) in packet_pairs.iter().enumerate() {
+        if pair.p1 <= pair.p2 {
+            sum += index + 1;
         }
-        index += 1;
     }
 
     // part 2
@@ -130,19 +364,22 @@ fn main() {
         all_packets.push(pair.p1);
         all_packets.push(pair.p2);
     }
-    all_packets.push(Packet::List(vec![Packet::List(vec![Packet::Value(2)])]));
-    all_packets.push(Packet::List(vec![Packet::List(vec![Packet::Value(6)])]));
-    sort_packets(&mut all_packets);
-    let divider_1_index = all_packets
-        .iter()
-        .position(|p| p == &Packet::List(vec![Packet::List(vec![Packet::Value(2)])]))
-        .unwrap()
-        + 1;
-    let divider_2_index = all_packets
-        .iter()
-        .position(|p| p == &Packet::List(vec![Packet::List(vec![Packet::Value(6)])]))
-        .unwrap()
-        + 1;
+
+    // no need to sort: a divider's position in the sorted list is one more than the number of packets
+    // that come before it. Divider 1 also comes before divider 2, so it shifts divider 2 back by one
+    let divider_1 = Packet::List(vec![Packet::List(vec![Packet::Value(2)])]);
+    let divider_2 = Packet::List(vec![Packet::List(vec![Packet::Value(6)])]);
+    let divider_1_index = all_packets.iter().filter(|p| **p < divider_1).count() + 1;
+    let divider_2_index = all_packets.iter().filter(|p| **p < divider_2).count() + 2;
+
+    if args.iter().any(|arg| arg == "--print-sorted") {
+        all_packets.push(divider_1);
+        all_packets.push(divider_2);
+        all_packets.sort();
+        for packet in &all_packets {
+            println!("{}", packet);
+        }
+    }
 
     // print answers
     println!("################################");
@@ -158,68 +395,159 @@ fn main() {
     );
 }
 
-fn parse_file(contents: &String) -> Vec<PairOfPackets> {
+// pairs are separated by one or more blank lines. Works with both LF and CRLF line endings
+fn parse_file(contents: &str) -> Result<Vec<PairOfPackets>, ParseError> {
     let mut pairs = vec![];
+    // (line number, line, packet) for each packet since the last blank line
+    let mut group: Vec<(usize, &str, Packet)> = vec![];
 
-    // split each pair by splitting on double newline (input file is CRLF)
-    for pair_str in contents.split("\r\n\r\n") {
-        let pair_split: Vec<&str> = pair_str.lines().collect();
-        pairs.push(PairOfPackets {
-            p1: Packet::parse(&pair_split.first().unwrap().to_string()),
-            p2: Packet::parse(&pair_split.last().unwrap().to_string()),
-        })
+    // an extra blank line at the end flushes the last group
+    for (index, line) in contents.lines().chain(std::iter::once("")).enumerate() {
+        let line_number = index + 1;
+        if !line.trim().is_empty() {
+            let packet = Packet::parse(line).map_err(|err| ParseError { line_number, ..err })?;
+            group.push((line_number, line, packet));
+            continue;
+        }
+
+        match group.len() {
+            0 => {}
+            2 => {
+                let (_, _, p2) = group.pop().unwrap();
+                let (_, _, p1) = group.pop().unwrap();
+                pairs.push(PairOfPackets { p1, p2 });
+            }
+            count => {
+                let (line_number, line, _) = group[0];
+                return Err(ParseError {
+                    line_number,
+                    column: 1,
+                    line: line.trim_end().to_string(),
+                    message: format!("Expected 2 packets between blank lines, but found {}", count),
+                });
+            }
+        }
     }
 
-    pairs
+    Ok(pairs)
 }
 
-fn is_in_order(left: &Packet, right: &Packet) -> bool {
-    compare_elements(left, right) <= 0
+fn parse_json_file(contents: &str) -> Result<Vec<PairOfPackets>, String> {
+    let json: JsonValue = serde_json::from_str(contents).map_err(|err| format!("Invalid JSON. {}", err))?;
+    let elements = match &json {
+        JsonValue::Array(elements) => elements,
+        _ => return Err(format!("$ is {}, but expected an array of packets", describe_json(&json))),
+    };
+    if elements.len() % 2 != 0 {
+        return Err(format!("Expected an even number of packets to make pairs, but found {}", elements.len()));
+    }
+
+    let mut pairs = vec![];
+    for (i, pair) in elements.chunks(2).enumerate() {
+        pairs.push(PairOfPackets {
+            p1: Packet::from_json(&pair[0], &format!("$[{}]", i * 2))?,
+            p2: Packet::from_json(&pair[1], &format!("$[{}]", i * 2 + 1))?,
+        });
+    }
+    Ok(pairs)
 }
 
-fn compare_elements(left: &Packet, right: &Packet) -> i32 {
-    match (left, right) {
-        (Packet::Value(l), Packet::Value(r)) => {
-            if l == r {
-                0
-            } else if l - r > 0 {
-                1
-            } else {
-                -1
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED: u64 = 0x2022_1213;
+    const RANDOM_PACKETS: usize = 1000;
+    const RANDOM_PACKET_MAX_DEPTH: usize = 6;
+    const RANDOM_PACKET_MAX_LEN: u64 = 5;
+
+    // tiny pseudo-random generator so the tests are repeatable without pulling in a crate
+    struct XorShift {
+        state: u64,
+    }
+
+    impl XorShift {
+        fn new(seed: u64) -> Self {
+            XorShift { state: seed.max(1) }
+        }
+
+        fn next_below(&mut self, bound: u64) -> u64 {
+            self.state ^= self.state << 13;
+            self.state ^= self.state >> 7;
+            self.state ^= self.state << 17;
+            self.state % bound
         }
-        (Packet::List(l), Packet::List(r)) => compare_lists(&l, &r),
-        (Packet::Value(l), Packet::List(r)) => compare_lists(&vec![Packet::Value(*l)], &r),
-        (Packet::List(l), Packet::Value(r)) => compare_lists(&l, &vec![Packet::Value(*r)]),
     }
-}
 
-fn compare_lists(left: &Vec<Packet>, right: &Vec<Packet>) -> i32 {
-    for i in 0..std::cmp::min(left.len(), right.len()) {
-        let diff = compare_elements(&left[i], &right[i]);
-        if diff < 0 {
-            return -1;
-        } else if diff > 0 {
-            return 1;
+    // the top level is always a list, like every packet in the input
+    fn random_packet(rng: &mut XorShift, depth: usize) -> Packet {
+        // deeper levels are more likely to be plain values so packets stay a reasonable size
+        if depth > 0 && (depth >= RANDOM_PACKET_MAX_DEPTH || rng.next_below(depth as u64 + 2) != 0) {
+            return Packet::Value(rng.next_below(40) as i32 - 10);
+        }
+
+        let len = rng.next_below(RANDOM_PACKET_MAX_LEN + 1);
+        Packet::List((0..len).map(|_| random_packet(rng, depth + 1)).collect())
+    }
+
+    // structural equality. Unlike ==, this tells 2, [2] and [[2]] apart
+    fn is_identical(l: &Packet, r: &Packet) -> bool {
+        match (l, r) {
+            (Packet::Value(l), Packet::Value(r)) => l == r,
+            (Packet::List(l), Packet::List(r)) => l.len() == r.len() && l.iter().zip(r).all(|(l, r)| is_identical(l, r)),
+            _ => false,
         }
     }
 
-    if left.len() == right.len() {
-        0
-    } else if left.len() as i32 - right.len() as i32 > 0 {
-        1
-    } else {
-        -1
+    fn random_packets() -> Vec<Packet> {
+        let mut rng = XorShift::new(SEED);
+        (0..RANDOM_PACKETS).map(|_| random_packet(&mut rng, 0)).collect()
     }
-}
 
-// simple bubble sort
-fn sort_packets(packets: &mut Vec<Packet>) {
-    for _ in 0..packets.len() {
-        for i in 0..packets.len() - 1 {
-            if !is_in_order(&packets[i], &packets[i + 1]) {
-                packets.swap(i, i + 1);
-            }
+    fn assert_display_round_trips(packet: &Packet) {
+        let written = packet.to_string();
+        let reparsed = match Packet::parse(&written) {
+            Ok(packet) => packet,
+            Err(err) => panic!("Could not read back {}. Reason: {}", written, err),
+        };
+        assert!(is_identical(&reparsed, packet), "Wrote {}, read back {}", written, reparsed);
+        assert_eq!(reparsed.to_string(), written);
+    }
+
+    fn assert_json_round_trips(packet: &Packet) {
+        let json = JsonValue::from(packet);
+        match Packet::try_from(&json) {
+            Ok(from_json) => assert!(is_identical(&from_json, packet), "Wrote {}, read back {}", json, from_json),
+            Err(err) => panic!("Could not read back {}. Reason: {}", json, err),
+        }
+    }
+
+    #[test]
+    fn random_packets_round_trip_through_display() {
+        for packet in random_packets() {
+            assert_display_round_trips(&packet);
+        }
+    }
+
+    #[test]
+    fn random_packets_round_trip_through_json() {
+        for packet in random_packets() {
+            assert_json_round_trips(&packet);
+        }
+    }
+
+    #[test]
+    fn example_packets_round_trip() {
+        let example = "[1,1,3,1,1]\n[1,1,5,1,1]\n\n[[1],[2,3,4]]\n[[1],4]\n\n[[]]\n[]\n\n[1,[2,[3,[4,[5,6,7]]]],8,9]\n[1,[2,[3,[4,[5,6,0]]]],8,9]\n";
+        let pairs = match parse_file(example) {
+            Ok(pairs) => pairs,
+            Err(err) => panic!("Could not parse the example. Reason: {}", err),
+        };
+        for pair in &pairs {
+            assert_display_round_trips(&pair.p1);
+            assert_display_round_trips(&pair.p2);
+            assert_json_round_trips(&pair.p1);
+            assert_json_round_trips(&pair.p2);
         }
     }
 }
@@ -1,10 +1,23 @@
 // Advent of Code 2022
 // Day 14
+//
+// Notes: Besides the answers, the cave can be drawn for one of the parts (--part 1 or --part 2, default 1):
+//  --show               print the final cave, cropped to the region containing rock and sand
+//  --pgm <file>         save the final cave as a greyscale PGM image
+//  --animate            draw the sand falling in the terminal
+//  --frames <dir>       save every frame of the animation as a PGM image
+//  --frame-every <n>    only draw every nth step of the simulation
+//  --flood-fill         solve part 2 by flood filling instead of simulating each piece of sand
+//  The cave itself can be changed with:
+//  --source <x,y>       where sand comes from (default 500,0). Repeat it for multiple sources
+//  --floor <y>          depth of the part 2 floor (default 2 below the lowest rock)
 
 use std::{
-    cmp::{max, min},
+    cmp::{max, min, Reverse},
+    collections::{HashMap, HashSet},
     env, fs,
 };
+mod render;
 
 fn main() {
     // get file path from commandline input
@@ -19,20 +32,77 @@ fn main() {
         Err(err) => panic!("Could not open input file {}. Reason: {}", input_file, err),
     };
 
+    let mut sources = match flag_values(&args, "--source").map(parse_coord).collect::<Result<Vec<(i64, i64)>, String>>() {
+        Ok(sources) => sources,
+        Err(err) => panic!("Could not read --source. Reason: {}", err),
+    };
+    if sources.is_empty() {
+        sources.push(DEFAULT_SAND_SOURCE);
+    }
+    let cave = match parse_file_to_cave(&file_contents, sources) {
+        Ok(cave) => cave,
+        Err(err) => panic!("Could not parse input file {}. Reason: {}", input_file, err),
+    };
+    let floor_y = match flag_value(&args, "--floor") {
+        Some(floor_y) => match floor_y.parse() {
+            Ok(floor_y) => floor_y,
+            Err(_) => panic!("--floor expects a number, got '{}'", floor_y),
+        },
+        None => cave.lowest_rock_y() + 2,
+    };
+
     // part 1
-    let mut cave_part_1 = parse_file_to_cave(&file_contents);
+    let mut cave_part_1 = cave.clone();
     let mut part_1_num_sand = 0;
     while cave_part_1.add_sand() {
         part_1_num_sand += 1;
     }
 
-    // part 2
-    let mut cave_part_2 = parse_file_to_cave(&file_contents);
-    let highest_y = cave_part_2.find_highest_rock_y();
-    cave_part_2.add_line_of_rocks((0, highest_y + 2), (cave_part_2.width - 1, highest_y + 2));
+    // part 2. --flood-fill counts the sand without simulating each grain
+    let mut cave_part_2 = cave.clone();
+    cave_part_2.set_floor(Some(floor_y));
     let mut part_2_num_sand = 0;
-    while cave_part_2.add_sand() {
-        part_2_num_sand += 1;
+    if args.iter().any(|arg| arg == "--flood-fill") {
+        part_2_num_sand = cave_part_2.count_sand_by_flood_fill();
+    } else {
+        while cave_part_2.add_sand() {
+            part_2_num_sand += 1;
+        }
+    }
+
+    // optional visuals for one of the two simulations, run on a fresh copy of its cave
+    let visual_part = match flag_value(&args, "--part") {
+        None | Some("1") => 1,
+        Some("2") => 2,
+        Some(other) => panic!("Unknown part '{}'. Expected 1 or 2", other),
+    };
+    let mut visual_cave = cave.clone();
+    if visual_part == 2 {
+        visual_cave.set_floor(Some(floor_y));
+    }
+
+    let frame_every = match flag_value(&args, "--frame-every").map(|n| n.parse::<usize>()) {
+        None => 1,
+        Some(Ok(n)) if n > 0 => n,
+        _ => panic!("--frame-every expects a number greater than 0"),
+    };
+    let animate = args.iter().any(|arg| arg == "--animate");
+    let frames_dir = flag_value(&args, "--frames");
+    if animate || frames_dir.is_some() {
+        render::animate(&mut visual_cave, animate, frames_dir, frame_every);
+    }
+
+    let show = args.iter().any(|arg| arg == "--show");
+    let pgm_file = flag_value(&args, "--pgm");
+    if show || pgm_file.is_some() {
+        while visual_cave.add_sand() {}
+        let viewport = visual_cave.auto_viewport();
+        if show {
+            println!("{}", render::render_text(&visual_cave, viewport));
+        }
+        if let Some(pgm_file) = pgm_file {
+            render::write_pgm(&visual_cave, viewport, pgm_file);
+        }
     }
 
     // print answers
@@ -49,7 +119,22 @@ fn main() {
     );
 }
 
-#[derive(Clone, Copy)]
+// returns the argument following the given flag, e.g. "2" for "--part 2"
+fn flag_value<'a>(args: &'a [String], flag: &'a str) -> Option<&'a str> {
+    flag_values(args, flag).next()
+}
+
+// returns the argument following every occurrence of the given flag
+fn flag_values<'a>(args: &'a [String], flag: &'a str) -> impl Iterator<Item = &'a str> {
+    args.iter().enumerate().filter(move |(_, arg)| *arg == flag).map(move |(index, _)| {
+        match args.get(index + 1) {
+            Some(value) => value.as_str(),
+            None => panic!("{} expects a value", flag),
+        }
+    })
+}
+
+#[derive(Clone, Copy, PartialEq)]
 enum Material {
     Air,
     Rock,
@@ -66,205 +151,350 @@ impl std::fmt::Display for Material {
     }
 }
 
+// smallest rectangle containing every rock and piece of sand, inclusive on all sides
+#[derive(Clone, Copy)]
+struct Bounds {
+    min_x: i64,
+    max_x: i64,
+    min_y: i64,
+    max_y: i64,
+}
+
+impl Bounds {
+    fn new(x: i64, y: i64) -> Self {
+        Bounds {
+            min_x: x,
+            max_x: x,
+            min_y: y,
+            max_y: y,
+        }
+    }
+
+    fn include(&mut self, x: i64, y: i64) {
+        self.min_x = min(self.min_x, x);
+        self.max_x = max(self.max_x, x);
+        self.min_y = min(self.min_y, y);
+        self.max_y = max(self.max_y, y);
+    }
+}
+
+const DEFAULT_SAND_SOURCE: (i64, i64) = (500, 0);
+
+// what happened after a single step of the simulation
+enum SandStep {
+    Fell,
+    CameToRest,
+    // the source's sand fell into the endless void or the source is blocked, so it won't add any more sand
+    SourceExhausted,
+    // every source is exhausted
+    Finished,
+}
+
+// the cave only stores the cells that aren't air, so coordinates can be negative or huge
+// and the floor (if there is one) stretches out forever in both directions
+#[derive(Clone)]
 struct Cave {
-    cave: Vec<Vec<Material>>,
-    width: usize,
-    height: usize,
+    cells: HashMap<(i64, i64), Material>,
+    bounds: Option<Bounds>,
+    lowest_rock_y: Option<i64>,
+    floor_y: Option<i64>,
+    // sources take turns adding a piece of sand
+    sources: Vec<(i64, i64)>,
+    current_source: usize,
+    exhausted: Vec<bool>,
+    // every position the last piece of sand from each source passed through, starting at the source.
+    // The next piece follows the exact same path until the last one came to rest, so it can start from there
+    fall_paths: Vec<Vec<(i64, i64)>>,
 }
 
 impl Cave {
-    const SAND_START_X: usize = 500;
-    const SAND_START_Y: usize = 0;
-
-    fn new(width: usize, height: usize) -> Self {
-        let mut cave: Vec<Vec<Material>> = vec![];
-        for _ in 0..height {
-            let mut row = vec![];
-            for _ in 0..width {
-                row.push(Material::Air);
-            }
-            cave.push(row);
+    fn new(sources: Vec<(i64, i64)>) -> Self {
+        if sources.is_empty() {
+            panic!("The cave needs at least one source of sand");
         }
+
         Cave {
-            cave,
-            width,
-            height,
+            cells: HashMap::new(),
+            bounds: None,
+            lowest_rock_y: None,
+            floor_y: None,
+            current_source: 0,
+            exhausted: vec![false; sources.len()],
+            fall_paths: vec![vec![]; sources.len()],
+            sources,
         }
     }
 
-    fn add_material(&mut self, x: usize, y: usize, mat: Material) {
-        self.cave[y][x] = mat;
+    // forgets every fall path and lets every source add sand again
+    fn reset_simulation(&mut self) {
+        self.fall_paths.iter_mut().for_each(|path| path.clear());
+        self.exhausted.iter_mut().for_each(|exhausted| *exhausted = false);
+    }
+
+    fn add_material(&mut self, x: i64, y: i64, mat: Material) {
+        if mat == Material::Sand {
+            // a fall path is only valid up to the first cell that is no longer air
+            for path in &mut self.fall_paths {
+                if let Some(index) = path.iter().position(|&cell| cell == (x, y)) {
+                    path.truncate(index);
+                }
+            }
+        } else {
+            // rocks (or removing things) could change where sand falls anywhere
+            self.reset_simulation();
+        }
+
+        if mat == Material::Air {
+            self.cells.remove(&(x, y));
+            return;
+        }
+
+        self.cells.insert((x, y), mat);
+        match &mut self.bounds {
+            Some(bounds) => bounds.include(x, y),
+            None => self.bounds = Some(Bounds::new(x, y)),
+        }
+        if mat == Material::Rock {
+            self.lowest_rock_y = Some(self.lowest_rock_y.map_or(y, |lowest| max(lowest, y)));
+        }
     }
 
-    fn material_at_x_y(&self, x: usize, y: usize) -> Option<&Material> {
-        if x > self.width - 1 || y > self.height - 1 {
-            return Option::None;
+    fn material_at_x_y(&self, x: i64, y: i64) -> Material {
+        if self.floor_y.is_some_and(|floor_y| y >= floor_y) {
+            return Material::Rock;
+        }
+        match self.cells.get(&(x, y)) {
+            Some(mat) => *mat,
+            None => Material::Air,
         }
-        Option::Some(&self.cave[y][x])
     }
 
-    fn add_line_of_rocks(&mut self, start: (usize, usize), end: (usize, usize)) {
-        let start_x: usize = start.0;
-        let start_y: usize = start.1;
-        let end_x: usize = end.0;
-        let end_y: usize = end.1;
+    // adds (or removes) an infinitely wide floor of rock at the given depth
+    fn set_floor(&mut self, floor_y: Option<i64>) {
+        self.floor_y = floor_y;
+        self.reset_simulation();
+    }
 
-        let x_diff = (start_x as i32 - end_x as i32).abs();
+    // rock lines can be horizontal, vertical, or diagonal at exactly 45 degrees
+    fn add_line_of_rocks(&mut self, start: (i64, i64), end: (i64, i64)) -> Result<(), String> {
+        let (start_x, start_y) = start;
+        let (end_x, end_y) = end;
+        let x_diff = end_x - start_x;
+        let y_diff = end_y - start_y;
+
+        if x_diff != 0 && y_diff != 0 && x_diff.abs() != y_diff.abs() {
+            return Err(format!(
+                "Rock line from {},{} to {},{} is neither horizontal, vertical, nor diagonal at 45 degrees",
+                start_x, start_y, end_x, end_y
+            ));
+        }
 
-        if x_diff != 0 {
-            // adding a horizontal line
-            for x in min(start_x, end_x)..=max(start_x, end_x) {
-                self.add_material(x, start_y, Material::Rock);
-            }
-        } else {
-            // adding a vertical line
-            for y in min(start_y, end_y)..=max(start_y, end_y) {
-                self.add_material(start_x, y, Material::Rock);
-            }
+        let steps = max(x_diff.abs(), y_diff.abs());
+        for step in 0..=steps {
+            let x = start_x + x_diff.signum() * step;
+            let y = start_y + y_diff.signum() * step;
+            self.add_material(x, y, Material::Rock);
         }
+        Ok(())
     }
 
-    // adds a piece of sand at (SAND_START_X, SAND_START_Y) and simulates it falling.
-    // returns true if the sand came to rest, or false if the sand falls into the endless void
-    // Increments Cave::num_sand if the sand came to rest
-    // TODO: please refactor this...
+    // adds a piece of sand from the next source and simulates it falling.
+    // returns true if the sand came to rest, or false once no source can add any more sand
+    // (all of their sand falls into the endless void or they are blocked)
     fn add_sand(&mut self) -> bool {
-        let mut sand_x = Cave::SAND_START_X;
-        let mut sand_y = Cave::SAND_START_Y;
+        loop {
+            match self.step_sand() {
+                SandStep::Fell | SandStep::SourceExhausted => continue,
+                SandStep::CameToRest => return true,
+                SandStep::Finished => return false,
+            }
+        }
+    }
 
-        // early return if sand already exists are starting location
-        match self.material_at_x_y(sand_x, sand_y).unwrap() {
-            Material::Sand | Material::Rock => return false,
-            _ => (),
+    // moves the current piece of sand by one cell, adding a new piece if the last one came to rest.
+    // A new piece resumes from where the previous piece from the same source was just before it came to rest
+    fn step_sand(&mut self) -> SandStep {
+        if self.exhausted.iter().all(|exhausted| *exhausted) {
+            return SandStep::Finished;
         }
 
-        while sand_y < self.height {
-            match self.material_at_x_y(sand_x, sand_y + 1) {
-                None => break,
-                Some(mat) => {
-                    match mat {
-                        // if space below is air, move down one
-                        Material::Air => {
-                            sand_y += 1;
-                        }
-                        _ => match self.material_at_x_y(sand_x - 1, sand_y + 1) {
-                            None => break,
-                            Some(mat) => {
-                                match mat {
-                                    // if space down+left is air, move there
-                                    Material::Air => {
-                                        sand_x -= 1;
-                                        sand_y += 1;
-                                    }
-                                    _ => match self.material_at_x_y(sand_x + 1, sand_y + 1) {
-                                        None => break,
-                                        Some(mat) => {
-                                            match mat {
-                                                // if space down+right is air, move there
-                                                Material::Air => {
-                                                    sand_x += 1;
-                                                    sand_y += 1;
-                                                }
-                                                _ => {
-                                                    // if we get here, the sand couldn't move down, left, or right. it has come to rest
-                                                    self.add_material(
-                                                        sand_x,
-                                                        sand_y,
-                                                        Material::Sand,
-                                                    );
-                                                    return true;
-                                                }
-                                            }
-                                        }
-                                    },
-                                }
-                            }
-                        },
-                    }
+        let source = self.current_source;
+        let (sand_x, sand_y) = match self.fall_paths[source].last() {
+            Some(&position) => position,
+            None => {
+                let (source_x, source_y) = self.sources[source];
+                if self.material_at_x_y(source_x, source_y) != Material::Air {
+                    return self.exhaust_current_source();
                 }
+                self.fall_paths[source].push((source_x, source_y));
+                return SandStep::Fell;
             }
+        };
+
+        // without a floor, anything below the lowest rock falls forever
+        if self.floor_y.is_none() && self.lowest_rock_y.is_none_or(|lowest| sand_y > lowest) {
+            return self.exhaust_current_source();
         }
-        false
-    }
 
-    fn find_highest_rock_y(&self) -> usize {
-        let mut highest = 0;
+        // try down, then down+left, then down+right
+        let next_x = [sand_x, sand_x - 1, sand_x + 1]
+            .into_iter()
+            .find(|x| self.material_at_x_y(*x, sand_y + 1) == Material::Air);
 
-        for y in 0..self.height {
-            for x in 0..self.width {
-                match self.material_at_x_y(x, y).unwrap() {
-                    Material::Rock => highest = y,
-                    _ => (),
-                }
+        match next_x {
+            Some(x) => {
+                self.fall_paths[source].push((x, sand_y + 1));
+                SandStep::Fell
+            }
+            None => {
+                // the sand couldn't move down, left, or right. it has come to rest
+                self.add_material(sand_x, sand_y, Material::Sand);
+                self.next_source();
+                SandStep::CameToRest
             }
         }
+    }
 
-        highest
+    fn exhaust_current_source(&mut self) -> SandStep {
+        self.exhausted[self.current_source] = true;
+        self.next_source();
+        SandStep::SourceExhausted
     }
 
-    #[allow(dead_code)]
-    fn print(&self) {
-        let mut s = String::new();
+    // moves on to the next source that can still add sand
+    fn next_source(&mut self) {
+        for offset in 1..=self.sources.len() {
+            let source = (self.current_source + offset) % self.sources.len();
+            if !self.exhausted[source] {
+                self.current_source = source;
+                return;
+            }
+        }
+    }
 
-        for y in 0..self.height {
-            for x in 0..self.width {
-                if x == Cave::SAND_START_X && y == Cave::SAND_START_Y {
-                    s.push('X');
-                } else {
-                    s += &format!("{}", self.cave[y][x]);
+    // counts how much sand comes to rest before every source is blocked, without simulating
+    // each piece. With a floor, sand ends up on every cell that is reachable from a source
+    // by moving down, down+left or down+right, so the rows can be filled in one at a time.
+    // Starts from an empty cave: any sand that was already added is ignored
+    fn count_sand_by_flood_fill(&self) -> usize {
+        let floor_y = match self.floor_y {
+            Some(floor_y) => floor_y,
+            None => panic!("Flood filling the cave requires a floor"),
+        };
+        let is_open = |x: i64, y: i64| y < floor_y && self.cells.get(&(x, y)) != Some(&Material::Rock);
+
+        // sources join in once the fill reaches their row
+        let mut sources = self.sources.clone();
+        sources.sort_by_key(|&(_, y)| Reverse(y));
+
+        let mut row: HashSet<i64> = HashSet::new();
+        let mut count = 0;
+        let mut y = match sources.last() {
+            Some(&(_, y)) => y,
+            None => return 0,
+        };
+        while !row.is_empty() || !sources.is_empty() {
+            while sources.last().is_some_and(|&(_, source_y)| source_y == y) {
+                let (source_x, _) = sources.pop().unwrap();
+                if is_open(source_x, y) {
+                    row.insert(source_x);
                 }
             }
-            s += "\n";
+
+            count += row.len();
+            row = row
+                .iter()
+                .flat_map(|x| [x - 1, *x, x + 1])
+                .filter(|x| is_open(*x, y + 1))
+                .collect();
+            y += 1;
+        }
+        count
+    }
+
+    fn lowest_rock_y(&self) -> i64 {
+        match self.lowest_rock_y {
+            Some(y) => y,
+            None => panic!("The cave doesn't contain any rocks"),
         }
+    }
 
-        println!("{}", s);
+    // smallest region containing every source, rock and piece of sand, and the floor
+    fn auto_viewport(&self) -> Bounds {
+        let (first_x, first_y) = self.sources[0];
+        let mut bounds = Bounds::new(first_x, first_y);
+        for &(x, y) in &self.sources {
+            bounds.include(x, y);
+        }
+        if let Some(cave_bounds) = self.bounds {
+            bounds.include(cave_bounds.min_x, cave_bounds.min_y);
+            bounds.include(cave_bounds.max_x, cave_bounds.max_y);
+        }
+        if let Some(floor_y) = self.floor_y {
+            bounds.include(bounds.min_x, floor_y);
+        }
+        bounds
     }
 }
 
-fn parse_file_to_cave(contents: &String) -> Cave {
-    let (highest_x, highest_y) = find_lows_and_highs(contents);
-    let mut cave = Cave::new(highest_x + 750, highest_y + 5); // adding a lot of padding so sand doesn't fall outside of the vector's bounds
+fn parse_file_to_cave(contents: &str, sources: Vec<(i64, i64)>) -> Result<Cave, String> {
+    let mut cave = Cave::new(sources);
 
     // parse input string
-    for line in contents.lines() {
-        let coords_strings = line.split("->").map(|c| c.trim());
-        let mut coords: Vec<(usize, usize)> = vec![];
-        for s in coords_strings {
-            let x: usize = s.split(',').nth(0).unwrap().parse().unwrap();
-            let y: usize = s.split(',').nth(1).unwrap().parse().unwrap();
-            coords.push((x, y));
+    for (index, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
         }
-        for i in 0..coords.len() - 1 {
-            cave.add_line_of_rocks(coords[i], coords[i + 1]);
+        let coords = line
+            .split("->")
+            .map(|c| parse_coord(c.trim()))
+            .collect::<Result<Vec<(i64, i64)>, String>>()
+            .map_err(|err| format!("line {}: {}", index + 1, err))?;
+        for pair in coords.windows(2) {
+            cave.add_line_of_rocks(pair[0], pair[1])
+                .map_err(|err| format!("line {}: {}", index + 1, err))?;
         }
     }
 
-    cave
+    Ok(cave)
 }
 
-// Since the input contains coordinates in the range around ~500,
-//  this fn finds the highest x and y values, so a 2d Vec can be
-//  created with the correct capacity. Return value is (highest_x, highest_y)
-fn find_lows_and_highs(contents: &String) -> (usize, usize) {
-    let mut highest_x = usize::MIN;
-    let mut highest_y = usize::MIN;
-
-    let coords: Vec<&str> = contents
-        .split(['\n', ' ', '-', '>'].as_ref())
-        .map(|c| c.trim())
-        .filter(|c| !c.is_empty())
-        .collect();
-
-    for c in coords {
-        let x: usize = c.split(',').nth(0).unwrap().parse().unwrap();
-        let y: usize = c.split(',').nth(1).unwrap().parse().unwrap();
-        if x > highest_x {
-            highest_x = x;
-        }
-        if y > highest_y {
-            highest_y = y;
+fn parse_coord(s: &str) -> Result<(i64, i64), String> {
+    let error = || format!("Could not parse coordinate '{}'. Expected 'x,y'", s);
+    let (x, y) = s.split_once(',').ok_or_else(error)?;
+    match (x.trim().parse(), y.trim().parse()) {
+        (Ok(x), Ok(y)) => Ok((x, y)),
+        _ => Err(error()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "498,4 -> 498,6 -> 496,6\n503,4 -> 502,4 -> 502,9 -> 494,9\n";
+
+    // sand at rest once the simulation stops, with the floor two below the lowest rock as in part 2
+    fn count_both_ways(sources: Vec<(i64, i64)>) -> (usize, usize) {
+        let mut cave = parse_file_to_cave(EXAMPLE, sources).unwrap();
+        cave.set_floor(Some(cave.lowest_rock_y() + 2));
+
+        let flood_filled = cave.count_sand_by_flood_fill();
+        let mut simulated = 0;
+        while cave.add_sand() {
+            simulated += 1;
         }
+        (simulated, flood_filled)
     }
 
-    (highest_x, highest_y)
+    #[test]
+    fn flood_fill_matches_simulation_with_one_source() {
+        assert_eq!(count_both_ways(vec![DEFAULT_SAND_SOURCE]), (93, 93));
+    }
+
+    #[test]
+    fn flood_fill_matches_simulation_with_several_sources() {
+        let (simulated, flood_filled) = count_both_ways(vec![(500, 0), (490, 2), (506, 5)]);
+        assert_eq!(simulated, flood_filled);
+    }
 }
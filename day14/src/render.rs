@@ -0,0 +1,107 @@
+use std::{collections::HashSet, fs, thread, time::Duration};
+
+use super::{Bounds, Cave, Material, SandStep};
+
+const FRAME_DELAY: Duration = Duration::from_millis(30);
+const CLEAR_TERMINAL: &str = "\x1b[2J\x1b[H";
+
+// char drawn for each cell. Sources of sand are an X and the paths of the
+// pieces of sand that are currently falling are drawn with ~
+fn glyph_at(cave: &Cave, falling: &HashSet<(i64, i64)>, x: i64, y: i64) -> char {
+    if cave.sources.contains(&(x, y)) {
+        return 'X';
+    }
+    match cave.material_at_x_y(x, y) {
+        Material::Air if falling.contains(&(x, y)) => '~',
+        Material::Air => '.',
+        Material::Rock => '#',
+        Material::Sand => 'o',
+    }
+}
+
+// grey level used for each glyph in PGM images
+fn shade_of(glyph: char) -> u8 {
+    match glyph {
+        '#' => 96,
+        'o' => 224,
+        '~' | 'X' => 255,
+        _ => 0,
+    }
+}
+
+// draws only the cells inside the viewport
+pub fn render_text(cave: &Cave, viewport: Bounds) -> String {
+    let falling: HashSet<(i64, i64)> = cave.fall_paths.iter().flatten().copied().collect();
+    let mut s = String::new();
+    for y in viewport.min_y..=viewport.max_y {
+        for x in viewport.min_x..=viewport.max_x {
+            s.push(glyph_at(cave, &falling, x, y));
+        }
+        s += "\n";
+    }
+    s
+}
+
+// binary greyscale PGM (P5) with one pixel per cell of the viewport
+pub fn render_pgm(cave: &Cave, viewport: Bounds) -> Vec<u8> {
+    let width = viewport.max_x - viewport.min_x + 1;
+    let height = viewport.max_y - viewport.min_y + 1;
+    let falling: HashSet<(i64, i64)> = cave.fall_paths.iter().flatten().copied().collect();
+
+    let mut image = format!("P5\n{} {}\n255\n", width, height).into_bytes();
+    for y in viewport.min_y..=viewport.max_y {
+        for x in viewport.min_x..=viewport.max_x {
+            image.push(shade_of(glyph_at(cave, &falling, x, y)));
+        }
+    }
+    image
+}
+
+pub fn write_pgm(cave: &Cave, viewport: Bounds, file: &str) {
+    if let Err(err) = fs::write(file, render_pgm(cave, viewport)) {
+        panic!("Could not write image {}. Reason: {}", file, err);
+    }
+}
+
+// runs the simulation until no more sand comes to rest, drawing a frame every frame_every steps
+// to the terminal and/or as numbered PGM images in frames_dir
+pub fn animate(cave: &mut Cave, to_terminal: bool, frames_dir: Option<&str>, frame_every: usize) {
+    // every frame uses the viewport of the finished cave so the picture doesn't jump around
+    let mut finished = cave.clone();
+    while finished.add_sand() {}
+    let viewport = finished.auto_viewport();
+
+    if let Some(dir) = frames_dir {
+        if let Err(err) = fs::create_dir_all(dir) {
+            panic!("Could not create frames directory {}. Reason: {}", dir, err);
+        }
+    }
+
+    let mut step = 0;
+    let mut frame = 0;
+    let mut num_sand = 0;
+    loop {
+        let result = cave.step_sand();
+        if let SandStep::CameToRest = result {
+            num_sand += 1;
+        }
+        let is_last = matches!(result, SandStep::Finished);
+
+        if step % frame_every == 0 || is_last {
+            if to_terminal {
+                print!("{}{}", CLEAR_TERMINAL, render_text(cave, viewport));
+                println!("{} pieces of sand have come to rest", num_sand);
+                thread::sleep(FRAME_DELAY);
+            }
+            if let Some(dir) = frames_dir {
+                write_pgm(cave, viewport, &format!("{}/frame_{:06}.pgm", dir, frame));
+            }
+            frame += 1;
+        }
+
+        if is_last {
+            break;
+        }
+        step += 1;
+    }
+}
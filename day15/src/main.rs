@@ -2,16 +2,54 @@
 // Day 15
 
 // Notes:
-//	This program is not optimized. Both parts of the puzzle are pretty slow, I assume because I'm storing many items in a HashSet
-//  I could have took the time to solve the puzzles without HashSets, but I already spent too much time on this day, so I decided to move on.
+//	Part 1 is solved with intervals: each sensor covers one range of x values on a given row, so the ranges are merged
+//    and measured instead of enumerating every cell.
+//  Part 2 reuses the part 1 ranges: every row of the search area is merged, clamped to the area and checked for gaps.
+//    That's one pass over the sensors per row instead of a pass per position. The first version walked every
+//    position on every diamond's perimeter and was pretty slow.
+//  The sensors can be drawn with --overview (whole area, down-sampled to --size <columns>x<rows>, default 80x40)
+//    or --exact (one char per position, for small areas like the example). --region <min_x,min_y,max_x,max_y>
+//    picks the area to draw and --ppm <file> saves the drawing as an image instead of printing it.
 
 use regex::Regex;
 use std::{collections::HashSet, env, fmt::Display, fs};
+mod render;
+
+// columns x rows of the --overview drawing when no --size is given
+const DEFAULT_OVERVIEW_SIZE: &str = "80x40";
+
+// the row and search area differ between the example in the puzzle text and the real input
+#[derive(Clone, Copy)]
+struct PuzzleConfig {
+    part_1_y: i32,
+    min_distress_coordinate: i32,
+    max_distress_coordinate: i32,
+    tuning_parameter_multiplier: i64,
+}
+
+impl PuzzleConfig {
+    const REAL: PuzzleConfig = PuzzleConfig {
+        part_1_y: 2000000,
+        min_distress_coordinate: 0,
+        max_distress_coordinate: 4000000,
+        tuning_parameter_multiplier: 4000000,
+    };
+
+    const EXAMPLE: PuzzleConfig = PuzzleConfig {
+        part_1_y: 10,
+        min_distress_coordinate: 0,
+        max_distress_coordinate: 20,
+        tuning_parameter_multiplier: 4000000,
+    };
 
-const PART_1_Y: i32 = 2000000;
-const MIN_DISTRESS_COORDINATE: i32 = 0;
-const MAX_DISTRESS_COORDINATE: i32 = 4000000;
-const TUNING_PARAMETER_MULTIPLIER: i64 = 4000000;
+    fn preset(name: &str) -> Self {
+        match name {
+            "real" => PuzzleConfig::REAL,
+            "example" => PuzzleConfig::EXAMPLE,
+            _ => panic!("Unknown preset '{}'. Expected 'real' or 'example'", name),
+        }
+    }
+}
 
 fn main() {
     // get file path from commandline input
@@ -26,73 +64,178 @@ fn main() {
         Err(err) => panic!("Could not open input file {}. Reason: {}", input_file, err),
     };
 
-    let sensors = parse_input(&file_contents);
-
-    // part 1
-    let mut non_beacon_coords = HashSet::<(i32, i32)>::new();
-    for s in &sensors {
-        non_beacon_coords.extend(s.get_non_beacon_coords_at_y(PART_1_Y));
+    // start from a preset (--preset real|example, default real) and override single values with
+    // --row <y>, --min <coordinate>, --max <coordinate> and --multiplier <n>
+    let mut config = PuzzleConfig::preset(flag_value(&args, "--preset").unwrap_or("real"));
+    if let Some(row) = parse_flag_value(&args, "--row") {
+        config.part_1_y = row;
     }
-
-    for s in &sensors {
-        if non_beacon_coords.contains(&(s.closest_beacon.x, s.closest_beacon.y)) {
-            non_beacon_coords.remove(&(s.closest_beacon.x, s.closest_beacon.y));
-        }
+    if let Some(min) = parse_flag_value(&args, "--min") {
+        config.min_distress_coordinate = min;
+    }
+    if let Some(max) = parse_flag_value(&args, "--max") {
+        config.max_distress_coordinate = max;
+    }
+    if let Some(multiplier) = parse_flag_value(&args, "--multiplier") {
+        config.tuning_parameter_multiplier = multiplier;
     }
 
-    // part 2
-    // go through each sensor's perimeter coordinates
-    // check if any of those coordinates are outside of every other sensor's radius
-    let mut distress_beacon = Beacon { x: 0, y: 0 };
-    let mut found = false;
-    for s1 in &sensors {
-        if found {
-            break;
-        }
-        for coord in s1.get_coords_at_sensors_radius() {
-            // skip this coordinate if it is outside the limit
-            if coord.0 > MAX_DISTRESS_COORDINATE
-                || coord.1 > MAX_DISTRESS_COORDINATE
-                || coord.0 < MIN_DISTRESS_COORDINATE
-                || coord.1 < MIN_DISTRESS_COORDINATE
-            {
-                continue;
-            }
+    let sensors = parse_input(&file_contents);
 
-            let mut inside_a_range = false;
-            for s2 in &sensors {
-                if s2.is_x_y_in_sensors_radius(coord.0, coord.1) {
-                    inside_a_range = true;
-                }
-            }
+    // part 1
+    let non_beacon_count = count_non_beacon_positions_at_y(&sensors, config.part_1_y);
+
+    // part 2. Not unwrapped until the answers are printed so the sensors can still be drawn
+    // when the search area is wrong
+    let distress_beacon = find_distress_beacon(
+        &sensors,
+        config.min_distress_coordinate,
+        config.max_distress_coordinate,
+    );
 
-            if !inside_a_range {
-                found = true;
-                distress_beacon = Beacon {
-                    x: coord.0,
-                    y: coord.1,
-                }
-            }
+    let overview = args.iter().any(|arg| arg == "--overview");
+    let exact = args.iter().any(|arg| arg == "--exact");
+    if overview || exact {
+        let region = match flag_value(&args, "--region") {
+            Some(region) => render::Region::parse(region),
+            None => render::Region::around_sensors(&sensors),
+        };
+        let map = if exact {
+            render::CoverageMap::exact(&sensors, region, distress_beacon.as_ref().ok())
+        } else {
+            let (columns, rows) = match flag_value(&args, "--size").unwrap_or(DEFAULT_OVERVIEW_SIZE).split_once('x') {
+                Some((columns, rows)) => match (columns.parse(), rows.parse()) {
+                    (Ok(columns), Ok(rows)) if columns > 0 && rows > 0 => (columns, rows),
+                    _ => panic!("--size expects <columns>x<rows>, e.g. 80x40"),
+                },
+                None => panic!("--size expects <columns>x<rows>, e.g. 80x40"),
+            };
+            render::CoverageMap::overview(&sensors, region, columns, rows, distress_beacon.as_ref().ok())
+        };
+
+        match flag_value(&args, "--ppm") {
+            Some(file) => map.write_ppm(file),
+            None => println!("{}", map.to_text()),
         }
     }
 
+    let distress_beacon = match distress_beacon {
+        Ok(beacon) => beacon,
+        Err(err) => panic!("Could not find the distress beacon. Reason: {}", err),
+    };
+
     // print answers
     println!("################################");
     println!("#### Advent of Code, Day 15 ####");
     println!("################################");
     println!(
         "Number of non-beacon positions at y={}: {}",
-        PART_1_Y,
-        non_beacon_coords.len()
+        config.part_1_y, non_beacon_count
     );
     println!("Location of distress signal: {}", distress_beacon);
     println!(
         "Distress signal's tuning frequency: {}",
-        distress_beacon.calculate_tuning_frequency()
+        distress_beacon.calculate_tuning_frequency(config.tuning_parameter_multiplier)
     );
 }
 
-fn parse_input(contents: &String) -> Vec<Sensor> {
+// returns the argument following the given flag, e.g. "example" for "--preset example"
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    let index = args.iter().position(|arg| arg == flag)?;
+    match args.get(index + 1) {
+        Some(value) => Some(value.as_str()),
+        None => panic!("{} expects a value", flag),
+    }
+}
+
+fn parse_flag_value<T: std::str::FromStr>(args: &[String], flag: &str) -> Option<T> {
+    let value = flag_value(args, flag)?;
+    match value.parse() {
+        Ok(value) => Some(value),
+        Err(_) => panic!("Could not parse the value '{}' given to {}", value, flag),
+    }
+}
+
+// counts the positions on row y where a beacon can't be, without looking at each position
+fn count_non_beacon_positions_at_y(sensors: &[Sensor], y: i32) -> i64 {
+    let ranges = merge_ranges(sensors.iter().filter_map(|s| s.get_coverage_at_y(y)).collect());
+    let covered: i64 = ranges
+        .iter()
+        .map(|(start, end)| *end as i64 - *start as i64 + 1)
+        .sum();
+
+    // beacons that are already on this row don't count. Several sensors can share a beacon
+    let beacons_on_row: HashSet<i32> = sensors
+        .iter()
+        .map(|s| &s.closest_beacon)
+        .filter(|b| b.y == y && ranges.iter().any(|(start, end)| (*start..=*end).contains(&b.x)))
+        .map(|b| b.x)
+        .collect();
+
+    covered - beacons_on_row.len() as i64
+}
+
+// merges inclusive ranges into a sorted list of disjoint ranges. Touching ranges like (1,3) and (4,6) are joined
+fn merge_ranges(mut ranges: Vec<(i32, i32)>) -> Vec<(i32, i32)> {
+    ranges.sort();
+
+    let mut merged: Vec<(i32, i32)> = vec![];
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some((_, last_end)) if start as i64 <= *last_end as i64 + 1 => {
+                *last_end = (*last_end).max(end);
+            }
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+// finds the one position within min..=max (in both x and y) that no sensor covers.
+// Errors if there is no such position, or more than one
+fn find_distress_beacon(sensors: &[Sensor], min: i32, max: i32) -> Result<Beacon, String> {
+    // the first couple of uncovered positions, for the error message, and how many there are in total
+    let mut uncovered: Vec<(i32, i32)> = vec![];
+    let mut uncovered_count: i64 = 0;
+
+    for y in min..=max {
+        let ranges = merge_ranges(sensors.iter().filter_map(|s| s.get_coverage_at_y(y)).collect());
+
+        // walk the ranges from left to right, collecting the gaps between them inside min..=max
+        let mut next_x = min as i64;
+        for (start, end) in ranges {
+            let (start, end) = (start as i64, end as i64);
+            if end < next_x {
+                continue;
+            }
+            let gap_end = start.min(max as i64 + 1);
+            if gap_end > next_x {
+                uncovered_count += gap_end - next_x;
+                uncovered.extend((next_x..gap_end).take(2).map(|x| (x as i32, y)));
+            }
+            next_x = next_x.max(end + 1);
+            if next_x > max as i64 {
+                break;
+            }
+        }
+        if next_x <= max as i64 {
+            uncovered_count += max as i64 - next_x + 1;
+            uncovered.extend((next_x..=max as i64).take(2).map(|x| (x as i32, y)));
+        }
+        uncovered.truncate(2);
+    }
+
+    match uncovered_count {
+        0 => Err(format!("every position between {} and {} is covered by a sensor", min, max)),
+        1 => Ok(Beacon { x: uncovered[0].0, y: uncovered[0].1 }),
+        _ => Err(format!(
+            "found {} uncovered positions, starting with ({},{}) and ({},{})",
+            uncovered_count, uncovered[0].0, uncovered[0].1, uncovered[1].0, uncovered[1].1
+        )),
+    }
+}
+
+fn parse_input(contents: &str) -> Vec<Sensor> {
     let mut sensors: Vec<Sensor> = vec![];
 
     let re = Regex::new(
@@ -140,69 +283,13 @@ impl Sensor {
         (self.x - self.closest_beacon.x).abs() + (self.y - self.closest_beacon.y).abs()
     }
 
-    fn get_non_beacon_coords_at_y(&self, y: i32) -> HashSet<(i32, i32)> {
-        let mut coords = HashSet::<(i32, i32)>::new();
-        let distance = self.get_distance_to_beacon();
-        let distance_from_y = (y - self.y).abs();
-
-        if distance >= distance_from_y {
-            for x in self.x - (distance - distance_from_y)..=self.x + (distance - distance_from_y) {
-                coords.insert((x, y));
-            }
+    // the inclusive range of x values this sensor covers on row y, if it reaches that row at all
+    fn get_coverage_at_y(&self, y: i32) -> Option<(i32, i32)> {
+        let reach = self.get_distance_to_beacon() - (y - self.y).abs();
+        if reach < 0 {
+            return None;
         }
-
-        coords
-    }
-
-    fn get_coords_at_sensors_radius(&self) -> HashSet<(i32, i32)> {
-        let mut coords = HashSet::<(i32, i32)>::new();
-        let mut x: i32;
-        let mut y: i32;
-
-        let radius: i32 = self.get_distance_to_beacon() + 1;
-
-        // We start in the upper point (x , y + radius)
-        x = self.x;
-        y = self.y + radius;
-
-        while x <= self.x + radius && y >= self.y {
-            coords.insert((x, y));
-            x += 1;
-            y -= 1;
-        }
-        x -= 1;
-        y += 1;
-
-        // Going left and down: from (x , y + radius) to (x , y - radius)
-        while x >= self.x && y >= self.y - radius {
-            coords.insert((x, y));
-            x -= 1;
-            y -= 1;
-        }
-        x += 1;
-        y += 1;
-
-        // Going left and up: from (x , y - radius) to (x - radius , y)
-        while x >= self.x - radius && y <= self.y {
-            coords.insert((x, y));
-            x -= 1;
-            y += 1;
-        }
-        x += 1;
-        y -= 1;
-
-        // Going right and up: from (x - radius , y) to (x , y + radius).
-        while x <= self.x && y <= self.y + radius {
-            coords.insert((x, y));
-            x += 1;
-            y += 1;
-        }
-
-        coords
-    }
-
-    fn is_x_y_in_sensors_radius(&self, x: i32, y: i32) -> bool {
-        ((self.x - x).abs() + (self.y - y).abs()) <= self.get_distance_to_beacon()
+        Some((self.x - reach, self.x + reach))
     }
 }
 
@@ -218,7 +305,58 @@ impl Display for Beacon {
 }
 
 impl Beacon {
-    fn calculate_tuning_frequency(&self) -> i64 {
-        (self.x as i64 * TUNING_PARAMETER_MULTIPLIER) + self.y as i64
+    fn calculate_tuning_frequency(&self, multiplier: i64) -> i64 {
+        (self.x as i64 * multiplier) + self.y as i64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "Sensor at x=2, y=18: closest beacon is at x=-2, y=15
+Sensor at x=9, y=16: closest beacon is at x=10, y=16
+Sensor at x=13, y=2: closest beacon is at x=15, y=3
+Sensor at x=12, y=14: closest beacon is at x=10, y=16
+Sensor at x=10, y=20: closest beacon is at x=10, y=16
+Sensor at x=14, y=17: closest beacon is at x=10, y=16
+Sensor at x=8, y=7: closest beacon is at x=2, y=10
+Sensor at x=2, y=0: closest beacon is at x=2, y=10
+Sensor at x=0, y=11: closest beacon is at x=2, y=10
+Sensor at x=20, y=14: closest beacon is at x=25, y=17
+Sensor at x=17, y=20: closest beacon is at x=21, y=22
+Sensor at x=16, y=7: closest beacon is at x=15, y=3
+Sensor at x=14, y=3: closest beacon is at x=15, y=3
+Sensor at x=20, y=1: closest beacon is at x=15, y=3";
+
+    fn find_in_example_area(sensors: &[Sensor]) -> Result<Beacon, String> {
+        let config = PuzzleConfig::EXAMPLE;
+        find_distress_beacon(sensors, config.min_distress_coordinate, config.max_distress_coordinate)
+    }
+
+    #[test]
+    fn finds_the_example_distress_beacon() {
+        let beacon = find_in_example_area(&parse_input(EXAMPLE)).unwrap();
+        assert_eq!((beacon.x, beacon.y), (14, 11));
+    }
+
+    // (10,10) is only touched by the falling edges of the small diamonds, never where a rising edge crosses one
+    #[test]
+    fn finds_a_beacon_between_falling_edges_only() {
+        let sensors = parse_input(
+            "Sensor at x=0, y=0: closest beacon is at x=18, y=0
+Sensor at x=20, y=20: closest beacon is at x=2, y=20
+Sensor at x=0, y=20: closest beacon is at x=18, y=20
+Sensor at x=20, y=0: closest beacon is at x=2, y=0
+Sensor at x=12, y=12: closest beacon is at x=15, y=12
+Sensor at x=8, y=8: closest beacon is at x=5, y=8",
+        );
+        let beacon = find_in_example_area(&sensors).unwrap();
+        assert_eq!((beacon.x, beacon.y), (10, 10));
+    }
+
+    #[test]
+    fn errors_when_more_than_one_position_is_uncovered() {
+        assert!(find_distress_beacon(&parse_input(EXAMPLE), 0, 100).is_err());
     }
 }
@@ -0,0 +1,234 @@
+use std::fs;
+
+use super::{merge_ranges, Beacon, Sensor};
+
+// exact views are drawn one position per char/pixel, so keep them to a sensible size
+const MAX_EXACT_POSITIONS: i64 = 4_000_000;
+// rows sampled inside each overview cell to estimate how much of it is covered
+const OVERVIEW_SAMPLE_ROWS: i64 = 8;
+// from uncovered to fully covered
+const DENSITY_GLYPHS: [char; 8] = ['.', ':', '-', '=', '+', '*', '%', '#'];
+
+// inclusive on all sides
+#[derive(Clone, Copy)]
+pub struct Region {
+    pub min_x: i64,
+    pub max_x: i64,
+    pub min_y: i64,
+    pub max_y: i64,
+}
+
+impl Region {
+    pub fn parse(s: &str) -> Self {
+        let values: Result<Vec<i64>, _> = s.split(',').map(|v| v.trim().parse()).collect();
+        match values.as_deref() {
+            Ok([min_x, min_y, max_x, max_y]) if min_x <= max_x && min_y <= max_y => Region {
+                min_x: *min_x,
+                max_x: *max_x,
+                min_y: *min_y,
+                max_y: *max_y,
+            },
+            _ => panic!("Could not parse region '{}'. Expected 'min_x,min_y,max_x,max_y'", s),
+        }
+    }
+
+    // the smallest region containing every sensor's whole diamond
+    pub fn around_sensors(sensors: &[Sensor]) -> Self {
+        let mut region = Region {
+            min_x: i64::MAX,
+            max_x: i64::MIN,
+            min_y: i64::MAX,
+            max_y: i64::MIN,
+        };
+        for s in sensors {
+            let radius = s.get_distance_to_beacon() as i64;
+            region.min_x = region.min_x.min(s.x as i64 - radius);
+            region.max_x = region.max_x.max(s.x as i64 + radius);
+            region.min_y = region.min_y.min(s.y as i64 - radius);
+            region.max_y = region.max_y.max(s.y as i64 + radius);
+        }
+        region
+    }
+
+    fn width(&self) -> i64 {
+        self.max_x - self.min_x + 1
+    }
+
+    fn height(&self) -> i64 {
+        self.max_y - self.min_y + 1
+    }
+}
+
+// what one char/pixel of a coverage map shows
+#[derive(Clone, Copy)]
+enum Cell {
+    // fraction of the cell that is covered by a sensor, from 0.0 to 1.0
+    Covered(f64),
+    Sensor,
+    Beacon,
+    DistressBeacon,
+}
+
+impl Cell {
+    fn glyph(&self) -> char {
+        match self {
+            Cell::Covered(density) => {
+                DENSITY_GLYPHS[(density * (DENSITY_GLYPHS.len() - 1) as f64).round() as usize]
+            }
+            Cell::Sensor => 'S',
+            Cell::Beacon => 'B',
+            Cell::DistressBeacon => 'X',
+        }
+    }
+
+    fn color(&self) -> [u8; 3] {
+        match self {
+            Cell::Covered(density) => {
+                let shade = |max: f64| (density * max) as u8;
+                [shade(40.0), shade(90.0), shade(200.0)]
+            }
+            Cell::Sensor => [230, 50, 50],
+            Cell::Beacon => [60, 220, 60],
+            Cell::DistressBeacon => [255, 230, 0],
+        }
+    }
+}
+
+// a grid of cells, each standing for a block of block_width x block_height positions
+pub struct CoverageMap {
+    region: Region,
+    block_width: i64,
+    block_height: i64,
+    cells: Vec<Vec<Cell>>,
+}
+
+impl CoverageMap {
+    // one cell per position. Panics if the region is too big for that
+    pub fn exact(sensors: &[Sensor], region: Region, distress_beacon: Option<&Beacon>) -> Self {
+        if region.width() * region.height() > MAX_EXACT_POSITIONS {
+            panic!(
+                "The region is {}x{} positions, which is too big to draw exactly. Pick a smaller --region or use --overview",
+                region.width(),
+                region.height()
+            );
+        }
+        Self::build(sensors, region, region.width(), region.height(), distress_beacon)
+    }
+
+    // the region squeezed into columns x rows cells, each showing how much of its block is covered
+    pub fn overview(
+        sensors: &[Sensor],
+        region: Region,
+        columns: i64,
+        rows: i64,
+        distress_beacon: Option<&Beacon>,
+    ) -> Self {
+        Self::build(
+            sensors,
+            region,
+            columns.min(region.width()),
+            rows.min(region.height()),
+            distress_beacon,
+        )
+    }
+
+    fn build(sensors: &[Sensor], region: Region, columns: i64, rows: i64, distress_beacon: Option<&Beacon>) -> Self {
+        // round up so the blocks cover the whole region, then drop the columns and rows
+        // that would start past its edge
+        let block_width = (region.width() + columns - 1) / columns;
+        let block_height = (region.height() + rows - 1) / rows;
+        let columns = (region.width() + block_width - 1) / block_width;
+        let rows = (region.height() + block_height - 1) / block_height;
+        let mut map = CoverageMap {
+            region,
+            block_width,
+            block_height,
+            cells: vec![],
+        };
+
+        for row in 0..rows {
+            let top = region.min_y + row * block_height;
+            let bottom = (top + block_height - 1).min(region.max_y);
+            let step = ((bottom - top + 1) / OVERVIEW_SAMPLE_ROWS).max(1);
+            let sample_ys: Vec<i64> = (top..=bottom).step_by(step as usize).collect();
+
+            // covered positions per column, summed over the sampled rows
+            let mut covered = vec![0i64; columns as usize];
+            for y in &sample_ys {
+                let ranges = merge_ranges(sensors.iter().filter_map(|s| s.get_coverage_at_y(*y as i32)).collect());
+                for (column, count) in covered.iter_mut().enumerate() {
+                    let left = region.min_x + column as i64 * block_width;
+                    let right = (left + block_width - 1).min(region.max_x);
+                    *count += ranges
+                        .iter()
+                        .map(|(start, end)| ((*end as i64).min(right) - (*start as i64).max(left) + 1).max(0))
+                        .sum::<i64>();
+                }
+            }
+
+            let cells = covered
+                .iter()
+                .enumerate()
+                .map(|(column, count)| {
+                    let left = region.min_x + column as i64 * block_width;
+                    let width = (left + block_width - 1).min(region.max_x) - left + 1;
+                    Cell::Covered(*count as f64 / (width * sample_ys.len() as i64) as f64)
+                })
+                .collect();
+            map.cells.push(cells);
+        }
+
+        // markers go on top of the coverage. The distress beacon wins over everything else
+        for s in sensors {
+            map.mark(s.closest_beacon.x, s.closest_beacon.y, Cell::Beacon);
+        }
+        for s in sensors {
+            map.mark(s.x, s.y, Cell::Sensor);
+        }
+        if let Some(beacon) = distress_beacon {
+            map.mark(beacon.x, beacon.y, Cell::DistressBeacon);
+        }
+        map
+    }
+
+    fn mark(&mut self, x: i32, y: i32, cell: Cell) {
+        let (x, y) = (x as i64, y as i64);
+        if x < self.region.min_x || x > self.region.max_x || y < self.region.min_y || y > self.region.max_y {
+            return;
+        }
+        let column = ((x - self.region.min_x) / self.block_width) as usize;
+        let row = ((y - self.region.min_y) / self.block_height) as usize;
+        self.cells[row][column] = cell;
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut s = format!(
+            "x {}..{}, y {}..{}, one char = {}x{} positions\n",
+            self.region.min_x, self.region.max_x, self.region.min_y, self.region.max_y, self.block_width, self.block_height
+        );
+        for row in &self.cells {
+            s.extend(row.iter().map(|cell| cell.glyph()));
+            s.push('\n');
+        }
+        s
+    }
+
+    // binary PPM (P6) with one pixel per cell
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let height = self.cells.len();
+        let width = self.cells.first().map_or(0, |row| row.len());
+        let mut image = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+        for row in &self.cells {
+            for cell in row {
+                image.extend(cell.color());
+            }
+        }
+        image
+    }
+
+    pub fn write_ppm(&self, file: &str) {
+        if let Err(err) = fs::write(file, self.to_ppm()) {
+            panic!("Could not write image {}. Reason: {}", file, err);
+        }
+    }
+}
@@ -1,8 +1,6 @@
 // Advent of Code 2022
 // Day 2
 
-// Note: The following code is quite ugly... proceed with caution
-
 // Notes:
 //  The elves are playing Rock Paper Scissors.
 //  You've been given a cheat sheet for it.
@@ -16,8 +14,25 @@
 // 1. What is the total score?
 // 2. Now the second column is the expected outcome. X = lose, Y = Draw, Z = Win. What is the new total score?
 
+// Notes on the solution:
+//  The shapes are numbered around a cycle (Rock = 0, Paper = 1, Scissors = 2) so each one beats the one before it.
+//    (mine - theirs) mod 3 is then 0 for a tie, 1 for a win and 2 for a loss, and the shape needed for an outcome
+//    is theirs + that same offset. No lookup tables of who beats whom are needed.
+//  The same works for any odd number of shapes N: each shape beats the (N - 1) / 2 shapes before it and loses to
+//    the ones after it. --variant picks the shapes: classic (default), lizard-spock or seven-way. Any other cycle
+//    can be given in order with --shapes, e.g. --shapes Rock,Spock,Paper,Lizard,Scissors (3 to 26 shapes).
+//    If several shapes give the wanted outcome, the one worth the most points is played.
+//  The points and letters can be changed with --shape-points 1,2,3 --outcome-points 0,3,6 (lose, tie, win),
+//    --their-letters ABC, --my-letters XYZ and --outcome-letters XYZ (lose, tie, win).
+//  The file is parsed once. The second column is kept as a letter and a Strategy decides what it means.
+//  --report lists every round with the running score, counts the wins, ties and losses and compares the score to
+//    always playing the best shape and to playing randomly. --strategy shape|outcome picks how the second column
+//    is read for it (default outcome).
+
 use std::env;
+use std::fmt;
 use std::fs;
+mod report;
 
 fn main() {
 	// get file path from commandline input
@@ -34,163 +49,358 @@ fn main() {
 		Err(_) => panic!("Could not find input file {}", input_file),
 	};
 
-	let games = process_file_contents(file_contents);
+	let rules = match RockPaperScissors::from_args(&args) {
+		Ok(rules) => rules,
+		Err(err) => panic!("Could not set up the game. Reason: {}", err),
+	};
+
+	let rounds = match process_file_contents(&file_contents, &rules) {
+		Ok(rounds) => rounds,
+		Err(err) => panic!("Could not parse the strategy guide. Reason: {}", err),
+	};
 
 	println!("#############################");
 	println!("### Advent of Code, Day 2 ###");
 	println!("#############################");
 	println!();
-	println!("Total score: {}", calculate_total_points(&games));
+	println!("Playing {}", rules.shapes.iter().map(|shape| shape.name.as_str()).collect::<Vec<&str>>().join(", "));
+	for (strategy, description) in [(Strategy::Shape, "my shape"), (Strategy::Outcome, "the outcome")] {
+		match play(&rounds, &rules, strategy) {
+			Ok(games) => println!("Total score with the second column as {}: {}",
+				description, calculate_total_points(&games, &rules)),
+			Err(err) => println!("The second column can't be read as {}: {}", description, err),
+		}
+	}
+
+	if args.iter().any(|arg| arg == "--report") {
+		let strategy = match flag_value(&args, "--strategy").unwrap_or("outcome") {
+			"shape" => Strategy::Shape,
+			"outcome" => Strategy::Outcome,
+			other => panic!("Unknown strategy '{}'. Expected shape or outcome", other),
+		};
+		match play(&rounds, &rules, strategy) {
+			Ok(games) => print!("\n{}", report::report(&rounds, &games, &rules)),
+			Err(err) => panic!("Could not play the strategy guide. Reason: {}", err),
+		}
+	}
 }
 
-#[derive(PartialEq)]
-#[derive(Debug)]
-enum RockPaperScissors {
-	Rock,
-	Paper,
-	Scissors,
+// returns the argument following the given flag, e.g. "lizard-spock" for "--variant lizard-spock"
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+	let index = args.iter().position(|arg| arg == flag)?;
+	match args.get(index + 1) {
+		Some(value) => Some(value.as_str()),
+		None => panic!("{} expects a value", flag),
+	}
 }
 
-#[derive(PartialEq)]
-#[derive(Debug)]
+// parses a comma separated list of points, e.g. "0,3,6"
+fn parse_points(input: &str) -> Result<Vec<u32>, String> {
+	input.split(',')
+		.map(|points| points.trim().parse::<u32>().map_err(|_| format!("'{}' is not a number of points", points)))
+		.collect()
+}
+
+// shapes of each variant, in cycle order. Each shape beats the ones just before it
+const CLASSIC_SHAPES: [&str; 3] = ["Rock", "Paper", "Scissors"];
+const LIZARD_SPOCK_SHAPES: [&str; 5] = ["Rock", "Spock", "Paper", "Lizard", "Scissors"];
+const SEVEN_WAY_SHAPES: [&str; 7] = ["Water", "Air", "Paper", "Sponge", "Scissors", "Fire", "Rock"];
+
+// a shape, as an index into RockPaperScissors::shapes
+#[derive(PartialEq, Debug, Clone, Copy)]
+struct Shape(usize);
+
+struct ShapeRule {
+	name: String,
+	points: u32,
+	their_letter: char,
+	my_letter: char,
+}
+
+// the rules of a game with an odd number of shapes arranged in a cycle
+struct RockPaperScissors {
+	shapes: Vec<ShapeRule>,
+	// points for losing, tying and winning
+	outcome_points: [u32; 3],
+	// letters meaning lose, tie and win
+	outcome_letters: [char; 3],
+}
+
+impl RockPaperScissors {
+	// shapes are worth 1, 2, 3... points in cycle order. Their letters start at A and mine end at Z
+	fn new(shape_names: &[&str]) -> Result<Self, String> {
+		let count = shape_names.len();
+		if count < 3 || count.is_multiple_of(2) {
+			return Err(format!("A game needs an odd number of shapes, at least 3, but got {}", count));
+		}
+		// one letter of the alphabet per shape
+		if count > 26 {
+			return Err(format!("A game can have at most 26 shapes, but got {}", count));
+		}
+		if let Some(name) = shape_names.iter().find(|name| name.trim().is_empty()) {
+			return Err(format!("Shape names can't be empty, got '{}'", name));
+		}
+		if (1..count).any(|i| shape_names[..i].contains(&shape_names[i])) {
+			return Err(format!("The shapes '{}' aren't all different", shape_names.join(",")));
+		}
+
+		let my_first_letter = b'Z' + 1 - count as u8;
+		Ok(RockPaperScissors {
+			shapes: shape_names.iter()
+				.enumerate()
+				.map(|(index, name)| ShapeRule {
+					name: name.to_string(),
+					points: index as u32 + 1,
+					their_letter: (b'A' + index as u8) as char,
+					my_letter: (my_first_letter + index as u8) as char,
+				})
+				.collect(),
+			outcome_points: [0, 3, 6],
+			outcome_letters: ['X', 'Y', 'Z'],
+		})
+	}
+
+	fn from_variant(variant: &str) -> Result<Self, String> {
+		match variant {
+			"classic" => RockPaperScissors::new(&CLASSIC_SHAPES),
+			"lizard-spock" => RockPaperScissors::new(&LIZARD_SPOCK_SHAPES),
+			"seven-way" => RockPaperScissors::new(&SEVEN_WAY_SHAPES),
+			_ => Err(format!("Unknown variant '{}'. Expected classic, lizard-spock or seven-way", variant)),
+		}
+	}
+
+	// the shapes given by --shapes or --variant with any points and letters overridden by the other flags
+	fn from_args(args: &[String]) -> Result<Self, String> {
+		let mut rules = match (flag_value(args, "--shapes"), flag_value(args, "--variant")) {
+			(Some(_), Some(_)) => return Err(String::from("Use either --shapes or --variant, not both")),
+			(Some(shapes), None) => RockPaperScissors::new(&shapes.split(',').map(|name| name.trim()).collect::<Vec<&str>>())?,
+			(None, variant) => RockPaperScissors::from_variant(variant.unwrap_or("classic"))?,
+		};
+
+		if let Some(points) = flag_value(args, "--shape-points") {
+			let points = parse_points(points)?;
+			if points.len() != rules.shapes.len() {
+				return Err(format!("--shape-points needs {} values, got {}", rules.shapes.len(), points.len()));
+			}
+			for (shape, points) in rules.shapes.iter_mut().zip(points) {
+				shape.points = points;
+			}
+		}
+		if let Some(points) = flag_value(args, "--outcome-points") {
+			rules.outcome_points = match parse_points(points)?.as_slice() {
+				[lose, tie, win] => [*lose, *tie, *win],
+				_ => return Err(String::from("--outcome-points needs 3 values: lose, tie, win")),
+			};
+		}
+		if let Some(letters) = flag_value(args, "--their-letters") {
+			rules.set_shape_letters(letters, |shape, letter| shape.their_letter = letter)?;
+		}
+		if let Some(letters) = flag_value(args, "--my-letters") {
+			rules.set_shape_letters(letters, |shape, letter| shape.my_letter = letter)?;
+		}
+		if let Some(letters) = flag_value(args, "--outcome-letters") {
+			rules.outcome_letters = match letters.chars().collect::<Vec<char>>().as_slice() {
+				[lose, tie, win] if lose != tie && tie != win && lose != win => [*lose, *tie, *win],
+				_ => return Err(String::from("--outcome-letters needs 3 different letters: lose, tie, win")),
+			};
+		}
+		Ok(rules)
+	}
+
+	fn set_shape_letters(&mut self, letters: &str, mut set: impl FnMut(&mut ShapeRule, char)) -> Result<(), String> {
+		let letters = letters.chars().collect::<Vec<char>>();
+		if letters.len() != self.shapes.len() {
+			return Err(format!("Expected {} letters, got '{}'", self.shapes.len(), letters.iter().collect::<String>()));
+		}
+		if (1..letters.len()).any(|i| letters[..i].contains(&letters[i])) {
+			return Err(format!("The letters '{}' aren't all different", letters.iter().collect::<String>()));
+		}
+		for (shape, letter) in self.shapes.iter_mut().zip(letters) {
+			set(shape, letter);
+		}
+		Ok(())
+	}
+
+	fn shape_count(&self) -> usize {
+		self.shapes.len()
+	}
+
+	// the outcome of playing mine against theirs
+	fn result(&self, mine: Shape, theirs: Shape) -> GameResult {
+		let offset = (mine.0 + self.shape_count() - theirs.0) % self.shape_count();
+		if offset == 0 {
+			GameResult::Tie
+		}
+		else if offset <= self.shape_count() / 2 {
+			GameResult::Win
+		}
+		else {
+			GameResult::Lose
+		}
+	}
+
+	// the shape to play against theirs to get the given result
+	fn shape_for_result(&self, theirs: Shape, result: GameResult) -> Shape {
+		(0..self.shape_count())
+			.map(Shape)
+			.filter(|mine| self.result(*mine, theirs) == result)
+			.max_by_key(|mine| (self.shapes[mine.0].points, std::cmp::Reverse(mine.0)))
+			.unwrap_or(theirs)
+	}
+
+	// my points for playing mine against theirs
+	fn points(&self, mine: Shape, theirs: Shape) -> u32 {
+		self.shape_points(mine) + self.result_points(self.result(mine, theirs))
+	}
+
+	fn shape_points(&self, shape: Shape) -> u32 {
+		self.shapes[shape.0].points
+	}
+
+	fn shape_name(&self, shape: Shape) -> &str {
+		&self.shapes[shape.0].name
+	}
+
+	fn result_points(&self, result: GameResult) -> u32 {
+		self.outcome_points[result.index()]
+	}
+
+	fn their_shape_for_letter(&self, letter: char) -> Option<Shape> {
+		self.shapes.iter().position(|shape| shape.their_letter == letter).map(Shape)
+	}
+
+	fn my_shape_for_letter(&self, letter: char) -> Option<Shape> {
+		self.shapes.iter().position(|shape| shape.my_letter == letter).map(Shape)
+	}
+
+	fn result_for_letter(&self, letter: char) -> Option<GameResult> {
+		let index = self.outcome_letters.iter().position(|outcome_letter| *outcome_letter == letter)?;
+		Some(GameResult::ALL[index])
+	}
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
 enum GameResult {
 	Win,
 	Lose,
 	Tie,
 }
 
-struct Game {
-	my_choice: RockPaperScissors,
-	their_choice: RockPaperScissors,
-	result: GameResult,
-	points: u32,
+impl GameResult {
+	// in the order of RockPaperScissors::outcome_points and outcome_letters
+	const ALL: [GameResult; 3] = [GameResult::Lose, GameResult::Tie, GameResult::Win];
+
+	fn index(&self) -> usize {
+		match self {
+			GameResult::Lose => 0,
+			GameResult::Tie => 1,
+			GameResult::Win => 2,
+		}
+	}
 }
 
-fn parse_to_rock_paper_scissors (input: &str) -> RockPaperScissors {
-	match input {
-		"A" | "X" => RockPaperScissors::Rock,
-		"B" | "Y" => RockPaperScissors::Paper,
-		_ => RockPaperScissors::Scissors,
+impl fmt::Display for GameResult {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.pad(match self {
+			GameResult::Win => "Win",
+			GameResult::Lose => "Lose",
+			GameResult::Tie => "Tie",
+		})
 	}
 }
 
-fn parse_to_game_result(input: &str) -> GameResult {
-	match input {
-		"X" => GameResult::Lose,
-		"Y" => GameResult::Tie,
-		_ => GameResult::Win,
+// what the second column of the strategy guide means
+#[derive(Clone, Copy)]
+enum Strategy {
+	// the letter is my shape, X = Rock, Y = Paper, Z = Scissors in the classic game
+	Shape,
+	// the letter is the outcome, X = Lose, Y = Tie, Z = Win
+	Outcome,
+}
+
+// one line of the strategy guide, before deciding what the second column means
+struct Round {
+	line_number: usize,
+	their_choice: Shape,
+	second_column: char,
+}
+
+impl Round {
+	fn my_choice(&self, rules: &RockPaperScissors, strategy: Strategy) -> Result<Shape, String> {
+		let choice = match strategy {
+			Strategy::Shape => rules.my_shape_for_letter(self.second_column),
+			Strategy::Outcome => rules.result_for_letter(self.second_column)
+				.map(|result| rules.shape_for_result(self.their_choice, result)),
+		};
+		choice.ok_or_else(|| format!("line {}: '{}' has no meaning", self.line_number, self.second_column))
 	}
 }
 
-fn fight(my_choice: &RockPaperScissors, their_choice: &RockPaperScissors) -> (GameResult, u32){
-	match *my_choice {
-		RockPaperScissors::Rock => {
-			let win_bonus: u32 = 1;
-			if *their_choice == RockPaperScissors::Rock {
-				(GameResult::Tie, 3 + win_bonus)
-			}
-			else if *their_choice == RockPaperScissors::Paper {
-				(GameResult::Lose, 0 + win_bonus)
-			}
-			else {
-				(GameResult::Win, 6 + win_bonus)
-			}
-		},
-		RockPaperScissors::Paper => {
-			let win_bonus: u32 = 2;
-			if *their_choice == RockPaperScissors::Rock {
-				(GameResult::Win, 6 + win_bonus)
-			}
-			else if *their_choice == RockPaperScissors::Paper {
-				(GameResult::Tie, 3 + win_bonus)
-			}
-			else {
-				(GameResult::Lose, 0 + win_bonus)
-			}
-		},
-		RockPaperScissors::Scissors => {
-			let win_bonus: u32 = 3;
-			if *their_choice == RockPaperScissors::Rock {
-				(GameResult::Lose, 0 + win_bonus)
-			}
-			else if *their_choice == RockPaperScissors::Paper {
-				(GameResult::Win, 6 + win_bonus)
-			}
-			else {
-				(GameResult::Tie, 3 + win_bonus)
-			}
-		}
+struct Game {
+	my_choice: Shape,
+	their_choice: Shape,
+}
+
+impl Game {
+	fn result(&self, rules: &RockPaperScissors) -> GameResult {
+		rules.result(self.my_choice, self.their_choice)
+	}
+
+	fn points(&self, rules: &RockPaperScissors) -> u32 {
+		rules.points(self.my_choice, self.their_choice)
 	}
 }
 
-fn get_my_choice_for_expected_result(their_choice: &RockPaperScissors, result: &GameResult) -> RockPaperScissors {
-	match *their_choice {
-		RockPaperScissors::Rock => {
-			if *result == GameResult::Win {
-				RockPaperScissors::Paper
-			}
-			else if *result == GameResult::Lose {
-				RockPaperScissors::Scissors
-			}
-			else {
-				RockPaperScissors::Rock
-			}
-		},
-		RockPaperScissors::Paper => {
-			if *result == GameResult::Win {
-				RockPaperScissors::Scissors
-			}
-			else if *result == GameResult::Lose {
-				RockPaperScissors::Rock
-			}
-			else {
-				RockPaperScissors::Paper
-			}
-		},
-		RockPaperScissors::Scissors => {
-			if *result == GameResult::Win {
-				RockPaperScissors::Rock
-			}
-			else if *result == GameResult::Lose {
-				RockPaperScissors::Paper
-			}
-			else {
-				RockPaperScissors::Scissors
-			}
-		}
+// returns the only char of input, or None if it is empty or longer
+fn parse_letter(input: &str) -> Option<char> {
+	let mut chars = input.chars();
+	match (chars.next(), chars.next()) {
+		(Some(letter), None) => Some(letter),
+		_ => None,
 	}
 }
 
-fn process_file_contents(contents: String) -> Vec<Game> {
-	let mut games = Vec::<Game>::new();
+fn process_file_contents(contents: &str, rules: &RockPaperScissors) -> Result<Vec<Round>, String> {
+	let mut rounds = Vec::<Round>::new();
 
-	for line in contents.lines() {
-		let choices = line.split(' ').collect::<Vec<&str>>();
-		let their_choice: RockPaperScissors = match choices.get(0) {
-			Some(r) => parse_to_rock_paper_scissors(r),
-			None => panic!("Your choice was not valid!")
+	for (line_number, line) in contents.lines().enumerate() {
+		let line = line.trim();
+		if line.is_empty() {
+			continue;
+		}
+
+		let columns = line.split_whitespace().collect::<Vec<&str>>();
+		let (their_letter, my_letter) = match columns.as_slice() {
+			[their_letter, my_letter] => (their_letter, my_letter),
+			_ => return Err(format!("line {}: expected two columns, found '{}'", line_number + 1, line)),
 		};
-		let required_result: GameResult = match choices.get(1) {
-			Some(r) => parse_to_game_result(r),
-			None => panic!("Invalid required game result!"),
+		let their_choice = match parse_letter(their_letter).and_then(|letter| rules.their_shape_for_letter(letter)) {
+			Some(shape) => shape,
+			None => return Err(format!("line {}: '{}' is not one of the opponent's shapes", line_number + 1, their_letter)),
+		};
+		let second_column = match parse_letter(my_letter) {
+			Some(letter) => letter,
+			None => return Err(format!("line {}: '{}' is not a single letter", line_number + 1, my_letter)),
 		};
-		let my_choice = get_my_choice_for_expected_result(&their_choice, &required_result);
-		let (result, points) = fight(&my_choice, &their_choice);
 
-		games.push(Game {
-			my_choice,
+		rounds.push(Round {
+			line_number: line_number + 1,
 			their_choice,
-			result,
-			points,
+			second_column,
 		});
 	}
-	games
+	Ok(rounds)
 }
 
-fn calculate_total_points(games: &Vec<Game>) -> u32 {
-	let mut total: u32 = 0;
-	for game in games {
-		total += game.points;
-	}
-	total
-}
\ No newline at end of file
+fn play(rounds: &[Round], rules: &RockPaperScissors, strategy: Strategy) -> Result<Vec<Game>, String> {
+	rounds.iter()
+		.map(|round| Ok(Game {
+			my_choice: round.my_choice(rules, strategy)?,
+			their_choice: round.their_choice,
+		}))
+		.collect()
+}
+
+fn calculate_total_points(games: &[Game], rules: &RockPaperScissors) -> u32 {
+	games.iter().map(|game| game.points(rules)).sum()
+}
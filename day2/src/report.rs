@@ -0,0 +1,57 @@
+use super::{Game, GameResult, RockPaperScissors, Round, Shape};
+
+// lists every round with the running score, then the win/tie/loss counts and how the
+// strategy guide compares to the best possible score and to playing randomly
+pub fn report(rounds: &[Round], games: &[Game], rules: &RockPaperScissors) -> String {
+	let name_width = rules.shapes.iter().map(|shape| shape.name.len()).max().unwrap_or(0);
+	let mut s = format!("{:>5}  {:>5}  {:<w$}  {:<w$}  {:<6}  {:>6}  {:>8}\n",
+		"round", "line", "them", "me", "result", "points", "total", w = name_width);
+
+	let mut total = 0;
+	let mut counts = [0; 3];
+	for (number, (round, game)) in rounds.iter().zip(games).enumerate() {
+		let result = game.result(rules);
+		let points = game.points(rules);
+		total += points;
+		counts[result.index()] += 1;
+
+		s += &format!("{:>5}  {:>5}  {:<w$}  {:<w$}  {:<6}  {:>6}  {:>8}\n",
+			number + 1,
+			round.line_number,
+			rules.shape_name(game.their_choice),
+			rules.shape_name(game.my_choice),
+			result,
+			points,
+			total,
+			w = name_width);
+	}
+
+	let optimal: u32 = games.iter().map(|game| best_points(rules, game.their_choice)).sum();
+	let random: f64 = games.iter().map(|game| expected_random_points(rules, game.their_choice)).sum();
+
+	s += "\n";
+	s += &format!("Wins: {}, ties: {}, losses: {}\n",
+		counts[GameResult::Win.index()], counts[GameResult::Tie.index()], counts[GameResult::Lose.index()]);
+	s += &format!("Strategy guide: {}\n", total);
+	s += &format!("Best shape every round: {} (the guide gets {:.1}% of it)\n", optimal, percent(total as f64, optimal as f64));
+	s += &format!("Random shapes, on average: {:.1} (the guide gets {:.1}% of it)\n", random, percent(total as f64, random));
+	s
+}
+
+// the most points any shape gets against theirs
+fn best_points(rules: &RockPaperScissors, theirs: Shape) -> u32 {
+	(0..rules.shape_count()).map(|mine| rules.points(Shape(mine), theirs)).max().unwrap_or(0)
+}
+
+// the average points of all the shapes against theirs, i.e. the expected points of picking one at random
+fn expected_random_points(rules: &RockPaperScissors, theirs: Shape) -> f64 {
+	let total: u32 = (0..rules.shape_count()).map(|mine| rules.points(Shape(mine), theirs)).sum();
+	total as f64 / rules.shape_count() as f64
+}
+
+fn percent(value: f64, of: f64) -> f64 {
+	match of {
+		0.0 => 0.0,
+		_ => value / of * 100.0,
+	}
+}
@@ -12,9 +12,17 @@
 // 2. Every group of 3 lines in the input file is a group of elves. There is 1 common item in each group.
 // 	  What is the total value of the common items throughout all the groups.
 
+// Notes on the solution:
+//  There are only 52 kinds of item, so a compartment is stored as a u64 with bit (value - 1) set for each item in it.
+//    The items two compartments or a group of rucksacks have in common are then just the AND of their masks.
+//  The group size can be changed with --group-size <n> (default 3).
+
 use std::env;
+use std::fmt;
 use std::fs;
 
+const DEFAULT_GROUP_SIZE: usize = 3;
+
 fn main() {
 	// get file path from commandline input
 	let args: Vec<String> = env::args().collect();
@@ -30,94 +38,194 @@ fn main() {
 		Err(_) => panic!("Could not find input file {}", input_file),
 	};
 
-	let rucksacks = process_file_contents(file_contents);
+	let group_size = match args.iter().position(|arg| arg == "--group-size").map(|index| args.get(index + 1)) {
+		Some(Some(size)) => match size.parse::<usize>() {
+			Ok(size) if size > 0 => size,
+			_ => panic!("--group-size expects a number greater than 0, got '{}'", size),
+		},
+		Some(None) => panic!("--group-size expects a value"),
+		None => DEFAULT_GROUP_SIZE,
+	};
+
+	let rucksacks = match process_file_contents(&file_contents) {
+		Ok(rucksacks) => rucksacks,
+		Err(err) => panic!("Could not read the rucksacks. Reason: {}", err),
+	};
+	let total_duplicates = match get_total_duplicates(&rucksacks) {
+		Ok(total) => total,
+		Err(err) => panic!("Could not find the duplicate items. Reason: {}", err),
+	};
+	let total_badge_values = match get_total_badge_values(&rucksacks, group_size) {
+		Ok(total) => total,
+		Err(err) => panic!("Could not find the badges. Reason: {}", err),
+	};
 
 	println!("#############################");
 	println!("### Advent of Code, Day 3 ###");
 	println!("#############################");
-	println!("total duplicates: {}", get_total_duplicates(&rucksacks));
-	println!("total badge values: {}", get_total_badge_values(&rucksacks));
+	println!("total duplicates: {}", total_duplicates);
+	println!("total badge values: {}", total_badge_values);
 }
 
-struct Rucksack {
-	compartment_1: String,
-	compartment_2: String,
+#[derive(Debug)]
+enum RucksackError {
+	OddLength { line_number: usize, length: usize },
+	InvalidItem { line_number: usize, item: char },
+	// what was searched, e.g. "the compartments on line 4" or "the rucksacks on lines 4-6", and what was found in common
+	NoCommonItem { lines: String },
+	MultipleCommonItems { lines: String, items: String },
+	IncompleteGroup { rucksacks: usize, group_size: usize },
 }
 
-impl Rucksack {
-	fn find_duplicate(&self) -> char {
-		let mut dupe: char = '\0';
-		for c1 in self.compartment_1.chars() {
-			for c2 in self.compartment_2.chars() {
-				if c1 == c2 {
-					dupe = c1;
-				}
+impl fmt::Display for RucksackError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			RucksackError::OddLength { line_number, length } =>
+				write!(f, "line {} has {} items, which can't be split into two equal compartments", line_number, length),
+			RucksackError::InvalidItem { line_number, item } =>
+				write!(f, "line {} has '{}', which is not an item (a-z or A-Z)", line_number, item),
+			RucksackError::NoCommonItem { lines } =>
+				write!(f, "{} have no item in common", lines),
+			RucksackError::MultipleCommonItems { lines, items } =>
+				write!(f, "{} have more than one item in common: {}", lines, items),
+			RucksackError::IncompleteGroup { rucksacks, group_size } =>
+				write!(f, "{} rucksacks can't be split into groups of {}", rucksacks, group_size),
+		}
+	}
+}
+
+// a set of items with bit (value - 1) set for each item in it
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct ItemSet(u64);
+
+impl ItemSet {
+	const ALL: ItemSet = ItemSet((1 << 52) - 1);
+
+	fn from_items(items: &str, line_number: usize) -> Result<Self, RucksackError> {
+		let mut set = ItemSet(0);
+		for item in items.chars() {
+			match get_item_value(item) {
+				Some(value) => set.0 |= 1 << (value - 1),
+				None => return Err(RucksackError::InvalidItem { line_number, item }),
 			}
 		}
-		dupe
+		Ok(set)
+	}
+
+	fn intersection(&self, other: ItemSet) -> ItemSet {
+		ItemSet(self.0 & other.0)
+	}
+
+	fn union(&self, other: ItemSet) -> ItemSet {
+		ItemSet(self.0 | other.0)
+	}
+
+	fn items(&self) -> impl Iterator<Item = char> + '_ {
+		(1..=52).filter(|value| self.0 & (1 << (value - 1)) != 0).filter_map(get_item_for_value)
+	}
+
+	// the only item in the set. lines says where the set came from for the error
+	fn single_item(&self, lines: String) -> Result<char, RucksackError> {
+		match self.0.count_ones() {
+			// count_ones() == 1 means there is an item, so None can't happen
+			1 => match self.items().next() {
+				Some(item) => Ok(item),
+				None => Err(RucksackError::NoCommonItem { lines }),
+			},
+			0 => Err(RucksackError::NoCommonItem { lines }),
+			_ => Err(RucksackError::MultipleCommonItems { lines, items: self.items().collect() }),
+		}
 	}
+}
+
+struct Rucksack {
+	line_number: usize,
+	compartment_1: ItemSet,
+	compartment_2: ItemSet,
+}
 
-	fn get_contents(&self) -> String {
-		self.compartment_1.clone() + &self.compartment_2
+impl Rucksack {
+	fn find_duplicate(&self) -> Result<char, RucksackError> {
+		self.compartment_1
+			.intersection(self.compartment_2)
+			.single_item(format!("the compartments on line {}", self.line_number))
+	}
+
+	fn get_contents(&self) -> ItemSet {
+		self.compartment_1.union(self.compartment_2)
 	}
 }
 
-fn process_file_contents(contents: String) -> Vec<Rucksack> {
+// the items that every one of the rucksacks has
+fn common_items(rucksacks: &[&Rucksack]) -> ItemSet {
+	rucksacks.iter().fold(ItemSet::ALL, |common, sack| common.intersection(sack.get_contents()))
+}
+
+fn process_file_contents(contents: &str) -> Result<Vec<Rucksack>, RucksackError> {
 	let mut rucksacks = Vec::<Rucksack>::new();
-	for line in contents.lines() {
-		let rucksack_length = line.len();
-		let (first_half, last_half) = line.split_at(rucksack_length/2);
-		rucksacks.push(Rucksack{
-			compartment_1: String::from(first_half),
-			compartment_2: String::from(last_half),
+	for (index, line) in contents.lines().enumerate() {
+		let line_number = index + 1;
+		let line = line.trim();
+		if line.is_empty() {
+			continue;
+		}
+
+		// items are all ASCII letters so bytes and chars line up, but check the letters first
+		// so that split_at can't land inside a multi-byte char
+		if let Some(item) = line.chars().find(|item| get_item_value(*item).is_none()) {
+			return Err(RucksackError::InvalidItem { line_number, item });
+		}
+		if !line.len().is_multiple_of(2) {
+			return Err(RucksackError::OddLength { line_number, length: line.len() });
+		}
+
+		let (first_half, last_half) = line.split_at(line.len() / 2);
+		rucksacks.push(Rucksack {
+			line_number,
+			compartment_1: ItemSet::from_items(first_half, line_number)?,
+			compartment_2: ItemSet::from_items(last_half, line_number)?,
 		});
 	}
-	rucksacks
+	Ok(rucksacks)
 }
 
-fn get_total_duplicates(rucksacks: &Vec<Rucksack>) -> u32 {
+fn get_total_duplicates(rucksacks: &[Rucksack]) -> Result<u32, RucksackError> {
 	let mut total: u32 = 0;
 	for sack in rucksacks {
-		total += get_item_value(sack.find_duplicate());
+		total += get_item_value(sack.find_duplicate()?).unwrap_or(0);
 	}
-	total
+	Ok(total)
 }
 
-fn get_total_badge_values(rucksacks: &Vec<Rucksack>) -> u32 {
+fn get_total_badge_values(rucksacks: &[Rucksack], group_size: usize) -> Result<u32, RucksackError> {
+	if !rucksacks.len().is_multiple_of(group_size) {
+		return Err(RucksackError::IncompleteGroup { rucksacks: rucksacks.len(), group_size });
+	}
+
 	let mut total: u32 = 0;
-	let num_groups = rucksacks.len()/3;
-	for i in 0..num_groups {
-		let dupe = find_duplicate_between_sacks(
-			&rucksacks[(i * 3) + 0],
-			&rucksacks[(i * 3) + 1],
-			&rucksacks[(i * 3) + 2]
-		);
-		total += get_item_value(dupe);
+	for group in rucksacks.chunks(group_size) {
+		let group = group.iter().collect::<Vec<&Rucksack>>();
+		let lines = format!("the rucksacks on lines {}-{}", group[0].line_number, group[group.len() - 1].line_number);
+		let badge = common_items(&group).single_item(lines)?;
+		total += get_item_value(badge).unwrap_or(0);
 	}
 
-	total
+	Ok(total)
 }
 
-fn find_duplicate_between_sacks(s1: &Rucksack, s2: &Rucksack, s3: &Rucksack) -> char {
-	let mut dupe: char = '\0';
-	for c1 in s1.get_contents().chars() {
-		for c2 in s2.get_contents().chars() {
-			for c3 in s3.get_contents().chars() {
-				if c1 == c2 && c2 == c3 {
-					dupe = c1;
-				}
-			}
-		}
+// a-z = 1-26 and A-Z = 27-52, None for anything else
+fn get_item_value(c: char) -> Option<u32> {
+	match c {
+		'a'..='z' => Some(c as u32 - 'a' as u32 + 1),
+		'A'..='Z' => Some(c as u32 - 'A' as u32 + 27),
+		_ => None,
 	}
-	dupe
 }
 
-fn get_item_value(c: char) -> u32 {
-	// calculate values of lowercase characters
-	if c as u32 >= 97 {
-		c as u32 - 96
+fn get_item_for_value(value: u32) -> Option<char> {
+	match value {
+		1..=26 => char::from_u32('a' as u32 + value - 1),
+		27..=52 => char::from_u32('A' as u32 + value - 27),
+		_ => None,
 	}
-	else {
-		c as u32 - 38
-	}
-}
\ No newline at end of file
+}
@@ -3,13 +3,18 @@
 
 // Notes:
 //  Each line in the input file contains a pair of sections for elves to clean
+//  A line can also list more than two sections (but not fewer), e.g. 2-4,3-7,6-8, for a bigger group of elves.
+//    A group is fully contained if one of its sections contains all the others and overlapping if any two overlap.
+//  --report lists, for every group, the sections more than one elf has to clean and the gaps nobody cleans
 
 // Questions:
 // 1. how many pairs have a section that fully contains the other section?
 // 2. how many pairs have overlapping sections
 
 use std::env;
+use std::fmt;
 use std::fs;
+mod report;
 
 fn main() {
 	// get file path from commandline input
@@ -26,81 +31,139 @@ fn main() {
 		Err(_) => panic!("Could not find input file {}", input_file),
 	};
 
-	let section_pairs: Vec<SectionPair> = process_file_contents(file_contents);
+	let assignments = match process_file_contents(&file_contents) {
+		Ok(assignments) => assignments,
+		Err(err) => panic!("Could not parse the assignments. Reason: {}", err),
+	};
 
 	println!("#############################");
 	println!("### Advent of Code, Day 4 ###");
 	println!("#############################");
-	println!("fully contained pairs: {}", count_fully_contained_pairs(&section_pairs));
-	println!("overlapping pairs: {}", count_overlapping_pairs(&section_pairs));
+	println!("fully contained pairs: {}", count_fully_contained_pairs(&assignments));
+	println!("overlapping pairs: {}", count_overlapping_pairs(&assignments));
+
+	if args.iter().any(|arg| arg == "--report") {
+		println!();
+		print!("{}", report::report(&assignments));
+	}
 }
 
-struct Section {
-	start: u32,
-	end: u32,
+// the section ids from start to end, inclusive
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Section {
+	pub start: u32,
+	pub end: u32,
 }
 
 impl Section {
-	fn parse(input: &str) -> Self {
-		let contents = input.split('-').collect::<Vec<&str>>();
-		Section {
-			start: contents[0].parse::<u32>().unwrap(),
-			end: contents[1].parse::<u32>().unwrap(),
+	fn parse(input: &str) -> Result<Self, String> {
+		let (start, end) = match input.trim().split_once('-') {
+			Some(bounds) => bounds,
+			None => return Err(format!("'{}' is not a section like 2-4", input)),
+		};
+		let parse_bound = |bound: &str| bound.parse::<u32>().map_err(|_| format!("'{}' is not a section id", bound));
+		let section = Section {
+			start: parse_bound(start)?,
+			end: parse_bound(end)?,
+		};
+		if section.start > section.end {
+			return Err(format!("section '{}' ends before it starts", input));
+		}
+		Ok(section)
+	}
+
+	// number of section ids
+	// u64 because 0-4294967295 has one more id than fits in a u32
+	pub fn id_count(&self) -> u64 {
+		self.end as u64 - self.start as u64 + 1
+	}
+
+	pub fn contains(&self, other: &Section) -> bool {
+		self.start <= other.start && other.end <= self.end
+	}
+
+	pub fn overlaps(&self, other: &Section) -> bool {
+		self.start <= other.end && other.start <= self.end
+	}
+
+	// the ids in both sections, if there are any
+	pub fn intersection(&self, other: &Section) -> Option<Section> {
+		match self.overlaps(other) {
+			true => Some(Section {
+				start: self.start.max(other.start),
+				end: self.end.min(other.end),
+			}),
+			false => None,
 		}
 	}
+
+	// the ids in either section, if that is one unbroken section, i.e. they overlap or touch
+	pub fn union(&self, other: &Section) -> Option<Section> {
+		let touching = self.start <= other.end.saturating_add(1) && other.start <= self.end.saturating_add(1);
+		match touching {
+			true => Some(Section {
+				start: self.start.min(other.start),
+				end: self.end.max(other.end),
+			}),
+			false => None,
+		}
+	}
+}
+
+impl fmt::Display for Section {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}-{}", self.start, self.end)
+	}
 }
 
-struct SectionPair {
-	section1: Section,
-	section2: Section,
+// the sections of one line of the input, one per elf
+pub struct Assignment {
+	pub line_number: usize,
+	pub sections: Vec<Section>,
 }
 
-impl SectionPair {
-	fn parse(input: &str) -> Self {
-		let contents = input.split(',').collect::<Vec<&str>>();
-		SectionPair {
-			section1: Section::parse(contents[0]),
-			section2: Section::parse(contents[1]),
+impl Assignment {
+	fn parse(input: &str, line_number: usize) -> Result<Self, String> {
+		let sections = input.split(',')
+			.map(Section::parse)
+			.collect::<Result<Vec<Section>, String>>()
+			.map_err(|err| format!("line {}: {}", line_number, err))?;
+		// a lone section would always count as fully contained in itself
+		if sections.len() < 2 {
+			return Err(format!("line {}: expected at least two sections, found '{}'", line_number, input));
 		}
+		Ok(Assignment { line_number, sections })
 	}
 
+	// one of the sections contains all the others
 	fn is_fully_contained(&self) -> bool {
-		(self.section1.start >= self.section2.start &&
-		self.section1.end <= self.section2.end) ||
-		(self.section2.start >= self.section1.start &&
-		self.section2.end <= self.section1.end)
+		self.sections.iter().any(|outer| self.sections.iter().all(|inner| outer.contains(inner)))
 	}
 
+	// at least two of the sections overlap
 	fn is_overlapping(&self) -> bool {
-		self.section1.start <= self.section2.end &&
-		self.section2.start <= self.section1.end
+		self.sections.iter()
+			.enumerate()
+			.any(|(i, a)| self.sections[i + 1..].iter().any(|b| a.overlaps(b)))
 	}
 }
 
-fn process_file_contents(contents: String) -> Vec<SectionPair>{
-	let mut pairs: Vec<SectionPair> = vec![];
-	for line in contents.lines() {
-		pairs.push(SectionPair::parse(line));
+fn process_file_contents(contents: &str) -> Result<Vec<Assignment>, String> {
+	let mut assignments: Vec<Assignment> = vec![];
+	for (index, line) in contents.lines().enumerate() {
+		let line = line.trim();
+		if line.is_empty() {
+			continue;
+		}
+		assignments.push(Assignment::parse(line, index + 1)?);
 	}
-	pairs
+	Ok(assignments)
 }
 
-fn count_fully_contained_pairs(pairs: &Vec<SectionPair>) -> u32 {
-	let mut total = 0;
-	for pair in pairs {
-		if pair.is_fully_contained() {
-			total += 1;
-		}
-	}
-	total
+fn count_fully_contained_pairs(assignments: &[Assignment]) -> usize {
+	assignments.iter().filter(|assignment| assignment.is_fully_contained()).count()
 }
 
-fn count_overlapping_pairs(pairs: &Vec<SectionPair>) -> u32 {
-	let mut total = 0;
-	for pair in pairs {
-		if pair.is_overlapping() {
-			total += 1;
-		}
-	}
-	total
-}
\ No newline at end of file
+fn count_overlapping_pairs(assignments: &[Assignment]) -> usize {
+	assignments.iter().filter(|assignment| assignment.is_overlapping()).count()
+}
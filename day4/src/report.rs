@@ -0,0 +1,103 @@
+use std::collections::BTreeMap;
+
+use super::{Assignment, Section};
+
+// splits the span of the sections into pieces that are each cleaned by the same number of elves,
+// from the first start to the last end. Pieces nobody cleans have a count of 0
+pub fn coverage(sections: &[Section]) -> Vec<(Section, usize)> {
+	// how the number of elves changes at each id. u64 so the id after u32::MAX fits
+	let mut changes: BTreeMap<u64, i64> = BTreeMap::new();
+	for section in sections {
+		*changes.entry(section.start as u64).or_default() += 1;
+		*changes.entry(section.end as u64 + 1).or_default() -= 1;
+	}
+
+	let mut pieces = vec![];
+	let mut count = 0;
+	let mut changes = changes.into_iter().peekable();
+	while let Some((start, change)) = changes.next() {
+		count += change;
+		if let Some((next, _)) = changes.peek() {
+			pieces.push((Section { start: start as u32, end: (next - 1) as u32 }, count as usize));
+		}
+	}
+	pieces
+}
+
+// joins overlapping and touching sections, sorted by start
+pub fn union_all(sections: &[Section]) -> Vec<Section> {
+	let mut sorted = sections.to_vec();
+	sorted.sort_by_key(|section| section.start);
+
+	let mut merged: Vec<Section> = vec![];
+	for section in sorted {
+		if let Some(last) = merged.last_mut() {
+			if let Some(union) = last.union(&section) {
+				*last = union;
+				continue;
+			}
+		}
+		merged.push(section);
+	}
+	merged
+}
+
+fn list(items: &[String]) -> String {
+	match items.is_empty() {
+		true => String::from("none"),
+		false => items.join(","),
+	}
+}
+
+fn list_sections(sections: &[Section]) -> String {
+	list(&sections.iter().map(|section| section.to_string()).collect::<Vec<String>>())
+}
+
+// one line per group: its sections, the sections more than one elf cleans (with how many),
+// the gaps no elf cleans and the union of all of them. Then totals over every group
+pub fn report(assignments: &[Assignment]) -> String {
+	let mut s = String::new();
+	let mut group_sizes: BTreeMap<usize, usize> = BTreeMap::new();
+	let mut groups_with_shared = 0;
+	let mut groups_with_gaps = 0;
+	let mut shared_ids: u64 = 0;
+	let mut gap_ids: u64 = 0;
+
+	for assignment in assignments {
+		*group_sizes.entry(assignment.sections.len()).or_default() += 1;
+
+		let pieces = coverage(&assignment.sections);
+		let shared = pieces.iter()
+			.filter(|(_, count)| *count > 1)
+			.map(|(section, count)| format!("{} (x{})", section, count))
+			.collect::<Vec<String>>();
+		let gaps = pieces.iter()
+			.filter(|(_, count)| *count == 0)
+			.map(|(section, _)| *section)
+			.collect::<Vec<Section>>();
+
+		if !shared.is_empty() {
+			groups_with_shared += 1;
+		}
+		if !gaps.is_empty() {
+			groups_with_gaps += 1;
+		}
+		shared_ids += pieces.iter().filter(|(_, count)| *count > 1).map(|(section, _)| section.id_count()).sum::<u64>();
+		gap_ids += gaps.iter().map(|gap| gap.id_count()).sum::<u64>();
+
+		s += &format!("line {:>4}: {} | shared: {} | gaps: {} | union: {}\n",
+			assignment.line_number,
+			list_sections(&assignment.sections),
+			list(&shared),
+			list_sections(&gaps),
+			list_sections(&union_all(&assignment.sections)));
+	}
+
+	s += "\n";
+	for (size, count) in &group_sizes {
+		s += &format!("groups of {}: {}\n", size, count);
+	}
+	s += &format!("groups where elves share sections: {} ({} section ids shared)\n", groups_with_shared, shared_ids);
+	s += &format!("groups with gaps: {} ({} section ids nobody cleans)\n", groups_with_gaps, gap_ids);
+	s
+}